@@ -0,0 +1,15 @@
+# Run `f` and change the directory of the shell to the last directory viewed
+# in it. Add to ~/.bashrc:
+#
+#     source /path/to/f/contrib/fcd.bash
+
+fcd() {
+    local tmp dir
+    tmp="$(mktemp)" || return
+    command f --choosedir "$tmp" "$@"
+    dir="$(cat -- "$tmp")"
+    rm -f -- "$tmp"
+    if [ -n "$dir" ] && [ -d "$dir" ] && [ "$dir" != "$PWD" ]; then
+        cd -- "$dir" || return
+    fi
+}
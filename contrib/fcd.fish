@@ -0,0 +1,12 @@
+# Run `f` and change the directory of the shell to the last directory viewed
+# in it. Copy this file to ~/.config/fish/functions/
+
+function fcd --description 'Run f and cd to the last directory viewed in it'
+    set -l tmp (mktemp); or return
+    command f --choosedir $tmp $argv
+    set -l dir (cat -- $tmp)
+    rm -f -- $tmp
+    if test -n "$dir"; and test -d "$dir"; and test "$dir" != "$PWD"
+        cd $dir
+    end
+end
@@ -0,0 +1,30 @@
+//! Configuration of `f` (`~/.config/f/master.conf` or `--config FILE`)
+//!
+//! ```toml
+//! use_human_units = true
+//! preview_files = true
+//! ```
+
+use crate::traits::Toml;
+use serde::{Deserialize, Serialize};
+
+/// Settings of `f`
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Conf {
+    /// Show sizes of the files in KBytes, MBytes, etc. (in bytes otherwise)
+    pub use_human_units: bool,
+    /// Show preview of the selected file at startup
+    pub preview_files: bool,
+}
+
+impl Toml for Conf {}
+
+impl Default for Conf {
+    fn default() -> Self {
+        Self {
+            use_human_units: true,
+            preview_files: true,
+        }
+    }
+}
@@ -0,0 +1,16 @@
+//! Paths used by `f` (relative ones are relative to the home directory)
+
+/// Directory with the configuration files
+pub const CONF_DIR: &str = ".config/f/";
+/// Main configuration file ([`crate::conf::Conf`])
+pub const MASTER_CONF: &str = ".config/f/master.conf";
+/// Version of the kernel
+pub const UNAME_FILE: &str = "/proc/version";
+/// Information about the Linux distribution
+pub const OS_RELEASE_FILE: &str = "/etc/os-release";
+/// Directory with the deleted files
+pub const RECYCLE_BIN_DIR: &str = ".local/share/f_bin/";
+/// Index of the deleted files
+pub const RECYCLE_BIN_META: &str = ".local/share/f_bin/f_bin.toml";
+/// Lock of the index of the deleted files
+pub const RECYCLE_BIN_LOCK: &str = ".local/share/f_bin/f_bin.lock";
@@ -0,0 +1,301 @@
+//! Detects filetypes
+
+use anyhow::{anyhow, Result};
+use std::{
+    ffi::OsString,
+    fmt::Display,
+    fs::{self, DirEntry},
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::recycle_bin::*;
+use crate::utils::{copy_recursive, real_path, remove_path};
+
+/// Some file from current listed directory
+#[derive(Debug, Clone)]
+pub struct FileEntry {
+    /// File name without full path
+    pub file_name: OsString,
+
+    /// Full path to the file
+    pub path: PathBuf,
+
+    /// File size in bytes
+    pub byte_size: u64,
+
+    /// Type of this file
+    pub file_type: FileType,
+
+    /// Name of the file begins with `.`
+    pub is_hidden: bool,
+
+    /// Path the symbolic link points to
+    pub link_target: Option<PathBuf>,
+
+    /// Modification time (of the link itself for the symbolic links)
+    pub mtime: Option<SystemTime>,
+}
+
+impl FileEntry {
+    /// Get information about the file from the directory listing
+    pub fn from_dir_entry(dir_entry: &DirEntry) -> Result<Self> {
+        let meta = dir_entry.metadata().map_err(|err| {
+            anyhow!(
+                "Failed to get metadata of '{}' file: {}",
+                dir_entry.path().display(),
+                err,
+            )
+        })?;
+        let file_type = FileType::from_fs_file_type(&meta.file_type());
+        let byte_size = meta.len();
+        let path = dir_entry.path();
+        let file_name = dir_entry.file_name();
+        let link_target = if meta.is_symlink() {
+            fs::read_link(&path).ok()
+        } else {
+            None
+        };
+
+        Ok(Self {
+            file_name,
+            path,
+            byte_size,
+            file_type,
+            is_hidden: dir_entry.file_name().to_string_lossy().starts_with('.'),
+            link_target,
+            mtime: meta.modified().ok(),
+        })
+    }
+
+    /// Check if this entry is the link to the parent directory (`.. [UP]`)
+    pub fn is_parent(&self) -> bool {
+        matches!(self.file_type, FileType::ParentDirectory)
+    }
+
+    /// Get the human file size from bytes
+    pub fn size(&self) -> FileSize {
+        FileSize::get_human_size(self.byte_size)
+    }
+
+    /// Remove files or directories
+    pub fn remove(&self) -> Result<()> {
+        remove_path(&self.path)
+    }
+
+    /// Get the path where this file will be copied or moved to. If `dest` is
+    /// an existing directory, the file is placed into it
+    pub fn dest_path<P: AsRef<Path>>(&self, dest: P) -> PathBuf {
+        let dest = dest.as_ref();
+        match (dest.is_dir(), self.path.file_name()) {
+            (true, Some(name)) => dest.join(name),
+            _ => dest.to_path_buf(),
+        }
+    }
+
+    fn check_dest(&self, dest: &Path) -> Result<()> {
+        if dest.symlink_metadata().is_ok() {
+            return Err(anyhow!("File '{}' already exists", dest.display()));
+        }
+
+        // Пути сравниваются без ссылок и `..`, иначе копирование `./a` в
+        // `/.../a` не распознаётся как копирование в себя
+        let src = real_path(&self.path)
+            .map_err(|err| anyhow!("Failed to access '{}': {}", self.path.display(), err))?;
+        let real_dest = real_path(dest)
+            .map_err(|err| anyhow!("Failed to access '{}': {}", dest.display(), err))?;
+        if real_dest.starts_with(&src) {
+            return Err(anyhow!(
+                "Cannot copy or move '{}' into itself",
+                self.path.display()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Copy file or directory to the `dest`. Returns path to the copy
+    pub fn copy_to<P: AsRef<Path>>(&self, dest: P) -> Result<PathBuf> {
+        let dest = self.dest_path(dest);
+        self.check_dest(&dest)?;
+
+        copy_recursive(&self.path, &dest).map_err(|err| {
+            anyhow!(
+                "Failed to copy '{}' to '{}': {}",
+                self.path.display(),
+                dest.display(),
+                err
+            )
+        })?;
+        Ok(dest)
+    }
+
+    /// Move (rename) file or directory to the `dest`. Returns the new path
+    pub fn move_to<P: AsRef<Path>>(&self, dest: P) -> Result<PathBuf> {
+        let dest = self.dest_path(dest);
+        self.check_dest(&dest)?;
+
+        match fs::rename(&self.path, &dest) {
+            Ok(_) => {}
+            // Перемещение между разными ФС невозможно сделать через rename(2)
+            Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+                self.copy_to(&dest)?;
+                self.remove()?;
+            }
+            Err(err) => {
+                return Err(anyhow!(
+                    "Failed to move '{}' to '{}': {}",
+                    self.path.display(),
+                    dest.display(),
+                    err
+                ))
+            }
+        }
+        Ok(dest)
+    }
+
+    /// Remove file/dir to recycle bin
+    pub fn remove_bin(&self) -> Result<()> {
+        let entry = RecycleBinEntry::new(&self.path);
+        entry.safe_delete()
+    }
+}
+
+/// Type of the file shown in the listing
+#[derive(Debug, Clone)]
+pub enum FileType {
+    /// Regular file
+    File,
+    /// Executable ELF or text script
+    FileExecutable,
+    /// Link to the parent directory (`.. [UP]`)
+    ParentDirectory,
+    /// Directory
+    Directory,
+    /// Symbolic link (only symbolic links supported yet)
+    Link,
+    /// Special (e.g. block, pipes) or unknown file type
+    Special,
+}
+
+impl FileType {
+    /// Convert the type from the file metadata
+    pub fn from_fs_file_type(ftype: &fs::FileType) -> Self {
+        if ftype.is_file() {
+            Self::File
+        } else if ftype.is_dir() {
+            Self::Directory
+        } else if ftype.is_symlink() {
+            Self::Link
+        } else {
+            Self::Special
+        }
+    }
+}
+
+impl Display for FileType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::File => "file",
+                Self::FileExecutable => "file*",
+                Self::ParentDirectory => "UP-DIR",
+                Self::Directory => "DIR",
+                Self::Link => "link",
+                Self::Special => "special",
+            }
+        )
+    }
+}
+
+/// Size of the file in the human-readable units (powers of 1024)
+pub enum FileSize {
+    /// Bytes
+    Bytes(u64),
+    /// Kibibytes
+    KBytes(f64),
+    /// Mebibytes
+    MBytes(f64),
+    /// Gibibytes
+    GBytes(f64),
+    /// Tebibytes
+    TBytes(f64),
+}
+
+impl FileSize {
+    /// Choose the largest unit in which the size is at least 1
+    pub fn get_human_size(byte_size: u64) -> Self {
+        let i: u64 = 2;
+        if byte_size >= i.pow(40) {
+            Self::TBytes(byte_size as f64 / 1024_f64.powf(4.))
+        } else if byte_size >= i.pow(30) {
+            Self::GBytes(byte_size as f64 / 1024_f64.powf(3.))
+        } else if byte_size >= i.pow(20) {
+            Self::MBytes(byte_size as f64 / 1024_f64.powf(2.))
+        } else if byte_size >= i.pow(10) {
+            Self::KBytes(byte_size as f64 / 1024.)
+        } else {
+            Self::Bytes(byte_size)
+        }
+    }
+}
+
+impl Display for FileSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Bytes(len) => format!("{len} Bytes"),
+                Self::KBytes(len) => format!("{len:.2} KBytes"),
+                Self::MBytes(len) => format!("{len:.2} MBytes"),
+                Self::GBytes(len) => format!("{len:.2} GBytes"),
+                Self::TBytes(len) => format!("{len:.2} TBytes"),
+            }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+
+    #[test]
+    fn check_dest_test() {
+        let dir = TempDir::new("check_dest");
+        fs::create_dir_all(dir.join("a")).unwrap();
+        fs::write(dir.join("a/file"), "abc").unwrap();
+        std::os::unix::fs::symlink(dir.join("a"), dir.join("link")).unwrap();
+
+        // Тот же путь относительно текущей директории
+        let cwd = std::env::current_dir().unwrap();
+        let relative = cwd
+            .components()
+            .skip(1)
+            .map(|_| Path::new(".."))
+            .collect::<PathBuf>()
+            .join(dir.strip_prefix("/").unwrap())
+            .join("a");
+        let entry = |path: PathBuf| FileEntry {
+            file_name: "a".into(),
+            path,
+            byte_size: 0,
+            file_type: FileType::Directory,
+            is_hidden: false,
+            link_target: None,
+            mtime: None,
+        };
+
+        assert!(entry(dir.join("a")).copy_to(&relative).is_err());
+        assert!(entry(relative.clone()).copy_to(dir.join("a")).is_err());
+        assert!(entry(relative).move_to(dir.join("a/../a")).is_err());
+        assert!(entry(dir.join("a")).copy_to(dir.join("link")).is_err());
+        assert!(!dir.join("a/a").exists());
+
+        let copy = entry(dir.join("a")).copy_to(dir.join("b")).unwrap();
+        assert_eq!(fs::read_to_string(copy.join("file")).unwrap(), "abc");
+    }
+}
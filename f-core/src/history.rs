@@ -0,0 +1,77 @@
+//! Write navigation history
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Maximum number of the directories remembered in each direction
+const MAX_LEN: usize = 100;
+
+/// Back and forward navigation history (like in the web browsers)
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct History {
+    #[serde(default)]
+    back: Vec<PathBuf>,
+    #[serde(default)]
+    forward: Vec<PathBuf>,
+}
+
+impl History {
+    /// Remember the directory which the user has left. Forward history is
+    /// cleared like in the web browsers. The oldest directories are
+    /// forgotten (history is saved in the session file)
+    pub fn push<P: AsRef<Path>>(&mut self, pth: P) {
+        if self.back.last().map(|p| p.as_path()) != Some(pth.as_ref()) {
+            self.back.push(pth.as_ref().to_path_buf());
+        }
+        if self.back.len() > MAX_LEN {
+            self.back.remove(0);
+        }
+        self.forward.clear();
+    }
+
+    /// Get the previous directory. `current` will be available by the
+    /// `forward()` call
+    pub fn back<P: AsRef<Path>>(&mut self, current: P) -> Option<PathBuf> {
+        let pth = self.back.pop()?;
+        self.forward.push(current.as_ref().to_path_buf());
+        Some(pth)
+    }
+
+    /// Get the next directory (after the `back()` call)
+    pub fn forward<P: AsRef<Path>>(&mut self, current: P) -> Option<PathBuf> {
+        let pth = self.forward.pop()?;
+        self.back.push(current.as_ref().to_path_buf());
+        Some(pth)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn history_test() {
+        let mut history = History::default();
+        assert_eq!(history.back("/a"), None);
+
+        history.push("/a");
+        history.push("/a");
+        history.push("/b");
+        assert_eq!(history.back("/c"), Some(PathBuf::from("/b")));
+        assert_eq!(history.back("/b"), Some(PathBuf::from("/a")));
+        assert_eq!(history.back("/a"), None);
+        assert_eq!(history.forward("/a"), Some(PathBuf::from("/b")));
+
+        // Переход в новую директорию очищает историю "вперёд"
+        history.push("/b");
+        assert_eq!(history.forward("/d"), None);
+        assert_eq!(history.back("/d"), Some(PathBuf::from("/b")));
+        assert_eq!(history.back("/b"), Some(PathBuf::from("/a")));
+
+        for n in 0..=MAX_LEN {
+            history.push(format!("/{n}"));
+        }
+        assert_eq!(history.back.len(), MAX_LEN);
+        assert_eq!(history.back[0], PathBuf::from("/1"));
+    }
+}
@@ -0,0 +1,35 @@
+//! Core of the `f` file manager without the TUI
+//!
+//! - [`ftype`] - entries of the directories and detecting of the file types;
+//! - [`recycle_bin`] - safe deleting files in the recycle bin and restoring
+//!   them;
+//! - [`utils`] - listing, copying and removing files and other helpers;
+//! - [`conf`] - configuration of `f`;
+//! - [`history`] - navigation history;
+//! - [`os_release`] - information about the installed Linux system;
+//! - `test_utils` - helpers for the tests (`test-utils` feature).
+//!
+//! ```no_run
+//! use f_core::{utils::read_dir, recycle_bin::RecycleBin};
+//!
+//! for entry in read_dir("/tmp", false).unwrap() {
+//!     println!("{} ({}, {})", entry.file_name.to_string_lossy(), entry.file_type, entry.size());
+//! }
+//! let rbin = RecycleBin::load().unwrap();
+//! println!("{} files in the recycle bin", rbin.entryes.len());
+//! ```
+
+#![warn(missing_docs)]
+
+pub mod conf;
+pub mod consts;
+pub mod ftype;
+pub mod history;
+pub mod os_release;
+pub mod recycle_bin;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
+pub mod traits;
+pub mod utils;
+
+pub use ftype::{FileEntry, FileSize, FileType};
@@ -62,6 +62,7 @@ impl OsRelease {
         Ok(data)
     }
 
+    /// Read `/etc/os-release` and `/proc/version`
     pub fn parse() -> Result<Self> {
         let os_release = Self::read_os_release()
             .map_err(|err| anyhow!("Failed to read '{}' file: {}", OS_RELEASE_FILE, err))?;
@@ -75,11 +76,7 @@ impl OsRelease {
                 .get("NAME")
                 .unwrap_or(&"UNIX".to_string())
                 .to_string(),
-            version: if let Some(ver) = os_release.get("VERSION") {
-                Some(ver.to_string())
-            } else {
-                None
-            },
+            version: os_release.get("VERSION").map(|ver| ver.to_string()),
             id: os_release
                 .get("ID")
                 .unwrap_or(&"unix".to_string())
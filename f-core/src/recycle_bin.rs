@@ -0,0 +1,302 @@
+//! Safe deleting files in recycle bin
+//!
+//! Index of the deleted files is locked while it's changed, so the TUI and
+//! the `f trash` commands may be run at the same time
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use uuid::Uuid;
+
+use crate::{
+    consts::{RECYCLE_BIN_DIR, RECYCLE_BIN_LOCK, RECYCLE_BIN_META},
+    traits::Toml,
+    utils::get_home,
+};
+
+/// Index of the deleted files
+#[derive(Deserialize, Serialize, Default)]
+pub struct RecycleBin {
+    /// Deleted files in the order of deleting
+    #[serde(rename = "entry")]
+    pub entryes: Vec<RecycleBinEntry>,
+}
+
+impl Toml for RecycleBin {}
+
+impl RecycleBin {
+    /// Lock the index until the returned file is closed
+    fn lock() -> Result<File> {
+        let pth = get_home().join(RECYCLE_BIN_LOCK);
+        let file = File::create(&pth)
+            .map_err(|err| anyhow!("Failed to open {}: {}", pth.display(), err))?;
+        file.lock()
+            .map_err(|err| anyhow!("Failed to lock {}: {}", pth.display(), err))?;
+
+        Ok(file)
+    }
+
+    fn read() -> Result<Self> {
+        let pth = get_home().join(RECYCLE_BIN_META);
+        match pth.exists() {
+            true => Self::parse(pth),
+            false => Ok(Self::default()),
+        }
+    }
+
+    /// Read the index
+    pub fn load() -> Result<Self> {
+        let _lock = Self::lock()?;
+        Self::read()
+    }
+
+    /// Change the index by `f` while it's locked. The index is written even
+    /// if `f` fails, because some files may be already changed
+    pub fn update<T>(f: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let _lock = Self::lock()?;
+        let mut rbin = Self::read()?;
+        let rslt = f(&mut rbin);
+        rbin.write(get_home().join(RECYCLE_BIN_META))?;
+
+        rslt
+    }
+
+    /// Find the entry by its ID (or the unique beginning of the ID) or by the
+    /// original path (the last deleted file with this path)
+    pub fn find(&self, key: &str) -> Result<usize> {
+        if let Some(idx) = self.entryes.iter().position(|e| e.deleted_name == key) {
+            return Ok(idx);
+        }
+
+        let by_id = self
+            .entryes
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.deleted_name.starts_with(key))
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        match by_id[..] {
+            [idx] => return Ok(idx),
+            [_, _, ..] => return Err(anyhow!("Ambiguous ID: '{key}'")),
+            [] => {}
+        }
+
+        let pth = std::path::absolute(key).unwrap_or(PathBuf::from(key));
+        self.entryes
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| Path::new(&e.orig_path) == pth)
+            .max_by_key(|(_, e)| e.deleted_at)
+            .map(|(idx, _)| idx)
+            .ok_or_else(|| anyhow!("No such file in the recycle bin: '{key}'"))
+    }
+
+    /// Move the file back to its original path
+    pub fn restore(&mut self, key: &str) -> Result<RecycleBinEntry> {
+        let idx = self.find(key)?;
+        self.entryes[idx].restore()?;
+
+        Ok(self.entryes.remove(idx))
+    }
+
+    /// Remove the file from the recycle bin permanently
+    pub fn remove(&mut self, key: &str) -> Result<RecycleBinEntry> {
+        let idx = self.find(key)?;
+        self.entryes[idx].remove_permanently()?;
+
+        Ok(self.entryes.remove(idx))
+    }
+
+    /// Remove all files (or the files deleted before `older_than`)
+    /// permanently. Returns number of removed files
+    pub fn empty(&mut self, older_than: Option<SystemTime>) -> Result<usize> {
+        let cutoff = older_than.map(|time| {
+            time.duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs())
+        });
+
+        let mut removed = 0;
+        let mut error = None;
+        self.entryes.retain(|entry| {
+            // Файлы, удалённые старыми версиями (с неизвестным временем
+            // удаления), считаются старыми
+            if cutoff.is_some_and(|cutoff| entry.deleted_at >= cutoff) {
+                return true;
+            }
+            match entry.remove_permanently() {
+                Ok(()) => {
+                    removed += 1;
+                    false
+                }
+                Err(why) => {
+                    error.get_or_insert(why);
+                    true
+                }
+            }
+        });
+
+        match error {
+            Some(why) => Err(why),
+            None => Ok(removed),
+        }
+    }
+}
+
+/// File in the recycle bin
+#[derive(Deserialize, Serialize, Clone)]
+pub struct RecycleBinEntry {
+    /// Path of the file before deleting
+    pub orig_path: String,
+    /// Name of the file in the recycle bin (UUID). It's used as the ID of
+    /// the deleted file
+    pub deleted_name: String,
+    /// Time of deleting (seconds since 1970-01-01, 0 if unknown)
+    #[serde(default)]
+    pub deleted_at: u64,
+}
+
+impl RecycleBinEntry {
+    /// New entry for deleting the `orig_pth` file. Relative path is stored
+    /// as absolute, so the file is restored to the same place from any
+    /// directory
+    pub fn new<P: AsRef<Path>>(orig_pth: P) -> Self {
+        let orig_pth = orig_pth.as_ref();
+        let orig_pth = std::path::absolute(orig_pth).unwrap_or(orig_pth.to_path_buf());
+        Self {
+            orig_path: orig_pth.display().to_string(),
+            deleted_name: Uuid::new_v4().simple().to_string(), // simple UUID without `-`
+            deleted_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |time| time.as_secs()),
+        }
+    }
+
+    /// Path of the deleted file in the recycle bin
+    pub fn bin_path(&self) -> PathBuf {
+        get_home().join(RECYCLE_BIN_DIR).join(&self.deleted_name)
+    }
+
+    /// Move the file to the recycle bin and add it to the index. Files in
+    /// the recycle bin directory itself are removed permanently
+    pub fn safe_delete(&self) -> Result<()> {
+        let home = get_home().join(RECYCLE_BIN_DIR);
+
+        let del_file = home.join(&self.deleted_name);
+        let Some(del_file_parent) = Path::new(&self.orig_path).parent() else {
+            return Err(anyhow!(
+                "Failed to safe remove the '{}' file: it has no parent directory",
+                &self.orig_path
+            ));
+        };
+
+        if del_file_parent == home {
+            return self.remove_permanently_orig();
+        }
+
+        RecycleBin::update(|rbin| {
+            fs::rename(&self.orig_path, &del_file).map_err(|err| {
+                anyhow!(
+                    "Failed to safe remove the '{}' file: {}",
+                    &self.orig_path,
+                    err,
+                )
+            })?;
+            rbin.entryes.push(self.clone());
+
+            Ok(())
+        })
+    }
+
+    fn remove_permanently_orig(&self) -> Result<()> {
+        let pth = Path::new(&self.orig_path);
+        if pth.is_dir() {
+            fs::remove_dir_all(pth)
+        } else {
+            fs::remove_file(pth)
+        }
+        .map_err(|err| anyhow!("Failed to remove '{}': {}", pth.display(), err))?;
+
+        Ok(())
+    }
+
+    /// Remove the file from the recycle bin directory (the index isn't
+    /// changed, see [`RecycleBin::remove`])
+    pub fn remove_permanently(&self) -> Result<()> {
+        let del_file = self.bin_path();
+        // Файл мог быть удалён из корзины вручную
+        if del_file.symlink_metadata().is_err() {
+            return Ok(());
+        }
+
+        if del_file.is_dir() {
+            fs::remove_dir_all(&del_file)
+        } else {
+            fs::remove_file(&del_file)
+        }
+        .map_err(|err| {
+            anyhow!(
+                "Failed to permanently remove the '{}' file: {}",
+                &self.orig_path,
+                err
+            )
+        })
+    }
+
+    /// Move the file back to its original path (its parent directories are
+    /// created if needed)
+    fn restore(&self) -> Result<()> {
+        let pth = Path::new(&self.orig_path);
+        if pth.symlink_metadata().is_ok() {
+            return Err(anyhow!("'{}' already exists", pth.display()));
+        }
+        if let Some(parent) = pth.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|err| anyhow!("Failed to create '{}': {}", parent.display(), err))?;
+        }
+
+        fs::rename(self.bin_path(), pth)
+            .map_err(|err| anyhow!("Failed to restore '{}': {}", pth.display(), err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_test() {
+        let entry = |pth: &str, id: &str, deleted_at| RecycleBinEntry {
+            orig_path: pth.to_string(),
+            deleted_name: id.to_string(),
+            deleted_at,
+        };
+        let rbin = RecycleBin {
+            entryes: vec![
+                entry("/a/x", "abc1", 10),
+                entry("/a/x", "abc2", 30),
+                entry("/a/y", "def", 20),
+            ],
+        };
+
+        assert_eq!(rbin.find("abc1").unwrap(), 0);
+        assert_eq!(rbin.find("d").unwrap(), 2);
+        assert!(rbin.find("abc").is_err());
+        assert_eq!(rbin.find("/a/x").unwrap(), 1);
+        assert!(rbin.find("/a/z").is_err());
+    }
+
+    #[test]
+    fn entry_path_test() {
+        let cwd = std::env::current_dir().unwrap();
+        let entry = RecycleBinEntry::new("a/b");
+        assert_eq!(Path::new(&entry.orig_path), cwd.join("a/b"));
+        assert_eq!(RecycleBinEntry::new("/a/b").orig_path, "/a/b");
+
+        let root = RecycleBinEntry::new("/");
+        assert!(root.safe_delete().is_err());
+    }
+}
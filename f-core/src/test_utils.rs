@@ -0,0 +1,61 @@
+//! Helpers for the tests of `f-core` and `f` (enabled by the `test-utils`
+//! feature)
+
+use std::{
+    fs,
+    ops::Deref,
+    path::{Path, PathBuf},
+};
+
+/// Temporary directory for the test. It's removed with all its contents when
+/// dropped, so the files are not left even if the test fails
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    /// Create the empty directory `f_<name>_<pid>` in the system temporary
+    /// directory. Files left by the previous run with the same PID are
+    /// removed
+    pub fn new(name: &str) -> Self {
+        let pth = std::env::temp_dir().join(format!("f_{name}_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&pth);
+        fs::create_dir_all(&pth).unwrap();
+        Self(pth)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        // Ошибка удаления не должна вызывать панику во время паники теста
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_dir_test() {
+        let pth = {
+            let dir = TempDir::new("temp_dir");
+            fs::write(dir.join("a"), "").unwrap();
+            assert!(dir.is_dir());
+            dir.to_path_buf()
+        };
+        assert!(!pth.exists());
+    }
+}
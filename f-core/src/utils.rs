@@ -0,0 +1,387 @@
+//! Some utilities and helpers
+
+use std::env::var;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::str::FromStr;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::ftype::{FileEntry, FileType};
+
+/// Get path to the user home directory
+pub fn get_home() -> PathBuf {
+    Path::new(&var("HOME").unwrap_or("/tmp".to_string())).to_path_buf()
+}
+
+/// Get path to the parent directory
+pub fn parent_dir<P: AsRef<Path>>(current: P) -> Result<FileEntry> {
+    let current_canon = fs::canonicalize(&current)?;
+
+    Ok(FileEntry {
+        file_name: OsString::from_str(".. [UP]").unwrap(),
+        path: current_canon
+            .clone()
+            .parent()
+            .unwrap_or(Path::new("/"))
+            .to_path_buf(),
+        byte_size: current_canon.metadata()?.len(),
+        is_hidden: false,
+        file_type: FileType::ParentDirectory,
+        link_target: None,
+        mtime: None,
+    })
+}
+
+/// Order of the files in the listing
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+pub enum SortBy {
+    /// By file name
+    #[default]
+    Name,
+    /// By extension (then by name)
+    Extension,
+    /// By size
+    Size,
+    /// By modification time
+    Modified,
+}
+
+impl SortBy {
+    /// Get the next sort order (for switching by the hotkey)
+    pub fn next(self) -> Self {
+        match self {
+            Self::Name => Self::Extension,
+            Self::Extension => Self::Size,
+            Self::Size => Self::Modified,
+            Self::Modified => Self::Name,
+        }
+    }
+}
+
+impl std::fmt::Display for SortBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Name => "name",
+                Self::Extension => "extension",
+                Self::Size => "size",
+                Self::Modified => "mtime",
+            }
+        )
+    }
+}
+
+/// Sort files. Parent directory (`.. [UP]`) always stays at the first place
+pub fn sort_entries(rows: &mut [FileEntry], sort: SortBy, reverse: bool) {
+    let start = match rows.first() {
+        Some(entry) if entry.is_parent() => 1,
+        _ => 0,
+    };
+    let rows = &mut rows[start..];
+
+    match sort {
+        SortBy::Name => rows.sort_by(|a, b| a.file_name.cmp(&b.file_name)),
+        SortBy::Extension => rows.sort_by(|a, b| {
+            (a.path.extension(), &a.file_name).cmp(&(b.path.extension(), &b.file_name))
+        }),
+        SortBy::Size => rows.sort_by_key(|entry| entry.byte_size),
+        SortBy::Modified => rows.sort_by_key(|entry| entry.mtime),
+    }
+    if reverse {
+        rows.reverse();
+    }
+}
+
+/// Read directory contents
+pub fn read_dir<P: AsRef<Path>>(pth: P, show_hidden: bool) -> Result<Vec<FileEntry>> {
+    let dir = fs::read_dir(&pth)?;
+    let mut rows = dir
+        // Используем только то, что можем прочитать
+        .filter_map(|entry| entry.ok())
+        // Используем только то, что можем обернуть в FileEntry
+        .filter_map(|entry| FileEntry::from_dir_entry(&entry).ok())
+        // Обрабатываем возможность отключения отображения скрытых файлов
+        .filter_map(|entry| {
+            if entry.is_hidden && !show_hidden {
+                None
+            } else {
+                Some(entry.clone())
+            }
+        })
+        // Собираем красивый вектор из этой поебени
+        .collect::<Vec<_>>();
+
+    /* Далее требуется выполнить сортировку вектора, потому что в исходном Vec файлы
+     * хранятся вразнобой. После сортировки добавляем в вектор путь, указывающий на
+     * родительский каталог. Если добавить путь к нему ДО сортировки, то этот каталог
+     * может оказаться не на первом месте, когда как он должен всегда находиться
+     * на первом месте.
+     */
+    rows.sort_by_key(|key| key.file_name.clone());
+
+    if pth.as_ref() != Path::new("/") {
+        rows.insert(0, parent_dir(&pth)?);
+        // rows.insert(1, FileEntry {
+        //     file_name: OsString::from_str("..").unwrap(),
+        //     path: Path::new("/").to_path_buf(),
+        //     byte_size: 0,
+        //     file_type: FileType::Directory,
+        //     is_hidden: false,
+        // });
+    }
+
+    // rows = rows
+    //     .iter()
+    //     .filter_map(|entry| {
+    //         if entry.is_hidden {
+    //             None
+    //         } else {
+    //             Some(entry.clone())
+    //         }
+    //     })
+    //     .collect::<Vec<_>>();
+
+    Ok(rows)
+}
+
+/// Check if the string matches the shell-like pattern (`*` - any string, `?` -
+/// any character)
+pub fn glob_match(pattern: &str, s: &str) -> bool {
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let s = s.chars().collect::<Vec<_>>();
+
+    let (mut p, mut i) = (0, 0);
+    // Позиция последней `*` в шаблоне и позиция в строке, с которой она
+    // начала сопоставляться
+    let mut star: Option<(usize, usize)> = None;
+
+    while i < s.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == s[i]) {
+            p += 1;
+            i += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, i));
+            p += 1;
+        } else if let Some((sp, si)) = star {
+            p = sp + 1;
+            i = si + 1;
+            star = Some((sp, si + 1));
+        } else {
+            return false;
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Remove file, symbolic link or directory with all its contents
+pub fn remove_path<P: AsRef<Path>>(pth: P) -> Result<()> {
+    let pth = pth.as_ref();
+    if fs::symlink_metadata(pth)?.is_dir() {
+        fs::remove_dir_all(pth)?;
+    } else {
+        fs::remove_file(pth)?;
+    }
+    Ok(())
+}
+
+/// Get the absolute path without links and `..` in the parent directories.
+/// Unlike `fs::canonicalize`, the last component isn't resolved (symbolic
+/// link stays the link)
+pub fn real_path<P: AsRef<Path>>(pth: P) -> std::io::Result<PathBuf> {
+    let pth = pth.as_ref();
+    match (pth.parent(), pth.file_name()) {
+        (Some(parent), Some(name)) if parent.as_os_str().is_empty() => {
+            Ok(fs::canonicalize(".")?.join(name))
+        }
+        (Some(parent), Some(name)) => Ok(fs::canonicalize(parent)?.join(name)),
+        _ => fs::canonicalize(pth),
+    }
+}
+
+/// Copy file, symbolic link or directory with all its contents
+pub fn copy_recursive<P: AsRef<Path>, Q: AsRef<Path>>(src: P, dst: Q) -> Result<()> {
+    let (src, dst) = (src.as_ref(), dst.as_ref());
+    let meta = fs::symlink_metadata(src)?;
+
+    if meta.is_symlink() {
+        std::os::unix::fs::symlink(fs::read_link(src)?, dst)?;
+    } else if meta.is_dir() {
+        fs::create_dir(dst)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_recursive(entry.path(), dst.join(entry.file_name()))?;
+        }
+        fs::set_permissions(dst, meta.permissions())?;
+    } else {
+        fs::copy(src, dst)?;
+    }
+
+    Ok(())
+}
+
+/// Get command for opening the file in the user's text editor (`$VISUAL`,
+/// `$EDITOR` or `vi`)
+pub fn editor_command<P: AsRef<Path>>(pth: P) -> Command {
+    let editor = var("VISUAL")
+        .or_else(|_| var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or("vi".to_string());
+
+    // Редактор может быть указан с аргументами (например, `code --wait`)
+    let mut words = editor.split_whitespace();
+    let mut cmd = Command::new(words.next().unwrap_or("vi"));
+    cmd.args(words).arg(pth.as_ref());
+    cmd
+}
+
+/// Expand `~` at the beginning of the path and environment variables (`$VAR`
+/// or `${VAR}`, unset variables are replaced by empty string like in shell)
+pub fn expand_path(s: &str) -> PathBuf {
+    let s = match s.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            format!("{}{rest}", get_home().display())
+        }
+        _ => s.to_string(),
+    };
+
+    let mut expanded = String::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            expanded.push(c);
+            continue;
+        }
+
+        let name = if chars.next_if_eq(&'{').is_some() {
+            chars.by_ref().take_while(|c| *c != '}').collect::<String>()
+        } else {
+            let mut name = String::new();
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                name.push(c);
+            }
+            name
+        };
+
+        if name.is_empty() {
+            expanded.push('$');
+        } else {
+            expanded.push_str(&var(name).unwrap_or_default());
+        }
+    }
+
+    PathBuf::from(expanded)
+}
+
+/// Get completions of the path to the directory. Relative paths are
+/// completed in the `base` directory. Every completion is the `s` with the
+/// full name of the directory and `/` at the end
+pub fn complete_dir<P: AsRef<Path>>(s: &str, base: P) -> Vec<String> {
+    let (dir, prefix) = match s.rfind('/') {
+        Some(idx) => (&s[..=idx], &s[idx + 1..]),
+        None => ("", s),
+    };
+    let Ok(entries) = fs::read_dir(base.as_ref().join(expand_path(dir))) else {
+        return Vec::new();
+    };
+
+    let mut completions = entries
+        .filter_map(|entry| entry.ok())
+        // `is_dir()` переходит по ссылкам, поэтому ссылки на директории тоже
+        // дополняются
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| {
+            name.starts_with(prefix) && (!name.starts_with('.') || prefix.starts_with('.'))
+        })
+        .map(|name| format!("{dir}{name}/"))
+        .collect::<Vec<_>>();
+    completions.sort();
+
+    completions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::TempDir;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn expand_path_test() {
+        let home = get_home();
+        assert_eq!(expand_path("~"), home);
+        assert_eq!(expand_path("~/a"), home.join("a"));
+        assert_eq!(expand_path("/a/~"), PathBuf::from("/a/~"));
+        assert_eq!(expand_path("$HOME/a"), home.join("a"));
+        assert_eq!(
+            expand_path("${HOME}a"),
+            PathBuf::from(format!("{}a", home.display()))
+        );
+        assert_eq!(expand_path("/a/$"), PathBuf::from("/a/$"));
+        assert_eq!(expand_path("/$F_UNSET_VARIABLE/a"), PathBuf::from("//a"));
+    }
+
+    #[test]
+    fn sort_entries_test() {
+        let entry = |name: &str, size, file_type| FileEntry {
+            file_name: name.into(),
+            path: PathBuf::from("/x").join(name),
+            byte_size: size,
+            file_type,
+            is_hidden: false,
+            link_target: None,
+            // Файлов нет на диске: время берётся только из списка
+            mtime: Some(UNIX_EPOCH + Duration::from_secs(100 - size)),
+        };
+        let mut rows = vec![
+            entry("..", 0, FileType::ParentDirectory),
+            entry("b.txt", 30, FileType::File),
+            entry("a.rs", 20, FileType::File),
+            entry("c.md", 10, FileType::File),
+        ];
+        let names = |rows: &[FileEntry]| {
+            rows.iter()
+                .map(|entry| entry.file_name.to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        sort_entries(&mut rows, SortBy::Name, false);
+        assert_eq!(names(&rows), ["..", "a.rs", "b.txt", "c.md"]);
+        sort_entries(&mut rows, SortBy::Extension, false);
+        assert_eq!(names(&rows), ["..", "c.md", "a.rs", "b.txt"]);
+        sort_entries(&mut rows, SortBy::Size, false);
+        assert_eq!(names(&rows), ["..", "c.md", "a.rs", "b.txt"]);
+        sort_entries(&mut rows, SortBy::Size, true);
+        assert_eq!(names(&rows), ["..", "b.txt", "a.rs", "c.md"]);
+        sort_entries(&mut rows, SortBy::Modified, false);
+        assert_eq!(names(&rows), ["..", "b.txt", "a.rs", "c.md"]);
+        sort_entries(&mut rows, SortBy::Modified, true);
+        assert_eq!(names(&rows), ["..", "c.md", "a.rs", "b.txt"]);
+        assert_eq!(SortBy::Modified.next(), SortBy::Name);
+    }
+
+    #[test]
+    fn complete_dir_test() {
+        let dir = TempDir::new("complete");
+        for sub in ["abc", "abd", "b", ".ab"] {
+            fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        fs::write(dir.join("abe"), "").unwrap();
+
+        assert_eq!(complete_dir("ab", &dir), ["abc/", "abd/"]);
+        assert_eq!(complete_dir(".a", &dir), [".ab/"]);
+        assert_eq!(
+            complete_dir(&format!("{}/b", dir.display()), "/"),
+            [format!("{}/b/", dir.display())]
+        );
+        assert!(complete_dir("x/", &dir).is_empty());
+    }
+}
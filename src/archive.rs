@@ -0,0 +1,811 @@
+//! Creating archives (tar, tar.gz, tar.zst and zip) from files and
+//! directories, reading and extracting them
+
+use crate::find::{civil_from_days, days_from_civil};
+
+use anyhow::{anyhow, Result};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    fs::{self, File, Metadata, OpenOptions, Permissions},
+    io::{self, BufReader, BufWriter, Read, Seek, Write},
+    ops::RangeInclusive,
+    os::unix::fs::{symlink, OpenOptionsExt, PermissionsExt},
+    path::{Component, Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+use tar::EntryType;
+use zip::{write::SimpleFileOptions, CompressionMethod, DateTime, ZipArchive, ZipWriter};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Tar,
+    TarGz,
+    TarZst,
+    Zip,
+}
+
+impl Format {
+    pub const ALL: [Self; 4] = [Self::Tar, Self::TarGz, Self::TarZst, Self::Zip];
+
+    /// Extension of the archive file (with dot)
+    pub fn ext(&self) -> &'static str {
+        match self {
+            Self::Tar => ".tar",
+            Self::TarGz => ".tar.gz",
+            Self::TarZst => ".tar.zst",
+            Self::Zip => ".zip",
+        }
+    }
+
+    /// Detect format of the archive by its file name
+    pub fn from_path<P: AsRef<Path>>(pth: P) -> Option<Self> {
+        let name = pth.as_ref().file_name()?.to_string_lossy().to_lowercase();
+        [
+            (".tar", Self::Tar),
+            (".tar.gz", Self::TarGz),
+            (".tgz", Self::TarGz),
+            (".tar.zst", Self::TarZst),
+            (".tzst", Self::TarZst),
+            (".zip", Self::Zip),
+        ]
+        .into_iter()
+        .find(|(ext, _)| name.ends_with(ext))
+        .map(|(_, format)| format)
+    }
+
+    /// Allowed compression levels (`None` if the format isn't compressed)
+    pub fn levels(&self) -> Option<RangeInclusive<u32>> {
+        match self {
+            Self::Tar => None,
+            Self::TarGz | Self::Zip => Some(0..=9),
+            Self::TarZst => Some(1..=19),
+        }
+    }
+
+    pub fn check_level(&self, level: u32) -> Result<()> {
+        match self.levels() {
+            Some(levels) if !levels.contains(&level) => Err(anyhow!(
+                "Wrong compression level: {level} (use {}-{})",
+                levels.start(),
+                levels.end()
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn default_level(&self) -> u32 {
+        match self {
+            Self::Tar => 0,
+            Self::TarGz | Self::Zip => 6,
+            Self::TarZst => 3,
+        }
+    }
+}
+
+/// State of the archive creation
+#[derive(Debug, Clone, Default)]
+pub struct Progress {
+    /// Size of the already archived data (in bytes)
+    pub done: u64,
+    /// Size of all archived files
+    pub total: u64,
+
+    /// Path of the current file in the archive
+    pub file: PathBuf,
+}
+
+/// Archived file
+struct Entry {
+    pth: PathBuf,
+    /// Path in the archive
+    name: PathBuf,
+    meta: Metadata,
+}
+
+/// Calls the progress callback while the archived data is read
+struct Tracker<'a> {
+    progress: Progress,
+    callback: &'a mut dyn FnMut(&Progress) -> bool,
+    /// Value of `progress.done` at the last call of the callback
+    reported: u64,
+}
+
+impl Tracker<'_> {
+    fn report(&mut self) -> io::Result<()> {
+        self.reported = self.progress.done;
+        if (self.callback)(&self.progress) {
+            Ok(())
+        } else {
+            // Не `Interrupted`: `io::copy()` повторяет чтение после такой
+            // ошибки
+            Err(io::Error::other("Cancelled"))
+        }
+    }
+
+    fn start(&mut self, entry: &Entry) -> io::Result<()> {
+        self.progress.file = entry.name.clone();
+        self.report()
+    }
+}
+
+struct TrackedReader<'a, 'b, R> {
+    inner: R,
+    tracker: &'a mut Tracker<'b>,
+}
+
+impl<R: Read> Read for TrackedReader<'_, '_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        self.tracker.progress.done += len as u64;
+        // Не чаще, чем раз в мегабайт, иначе отчёты будут медленнее архивации
+        if self.tracker.progress.done - self.tracker.reported >= 1 << 20 {
+            self.tracker.report()?;
+        }
+        Ok(len)
+    }
+}
+
+/// Collect the archived files. Directories are walked recursively without
+/// following symbolic links
+fn collect(
+    pth: &Path,
+    name: PathBuf,
+    exclude_hidden: bool,
+    entries: &mut Vec<Entry>,
+) -> Result<()> {
+    let meta = fs::symlink_metadata(pth)
+        .map_err(|why| anyhow!("Failed to read '{}': {}", pth.display(), why))?;
+    let is_dir = meta.is_dir();
+    entries.push(Entry {
+        pth: pth.to_path_buf(),
+        name: name.clone(),
+        meta,
+    });
+
+    if is_dir {
+        let mut children = fs::read_dir(pth)
+            .and_then(|dir| dir.collect::<io::Result<Vec<_>>>())
+            .map_err(|why| anyhow!("Failed to read '{}': {}", pth.display(), why))?;
+        children.sort_by_key(|entry| entry.file_name());
+
+        for child in children {
+            let child_name = child.file_name();
+            if exclude_hidden && child_name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            collect(
+                &child.path(),
+                name.join(child_name),
+                exclude_hidden,
+                entries,
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Convert time to the zip format (zip can't store dates before 1980)
+fn zip_time(time: SystemTime) -> DateTime {
+    let secs = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let (days, secs) = (secs / 86400, secs % 86400);
+
+    let (year, month, day) = civil_from_days(days as i64);
+
+    DateTime::from_date_and_time(
+        year as u16,
+        month as u8,
+        day as u8,
+        (secs / 3600) as u8,
+        (secs % 3600 / 60) as u8,
+        (secs % 60) as u8,
+    )
+    .unwrap_or_default()
+}
+
+fn write_tar<W: Write>(writer: W, entries: &[Entry], tracker: &mut Tracker) -> Result<W> {
+    let mut builder = tar::Builder::new(writer);
+
+    for entry in entries {
+        tracker.start(entry)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_metadata(&entry.meta);
+
+        if entry.meta.is_symlink() {
+            header.set_size(0);
+            builder.append_link(&mut header, &entry.name, fs::read_link(&entry.pth)?)?;
+        } else if entry.meta.is_file() {
+            // Файл мог измениться после подсчёта размера, а в заголовке tar
+            // он уже записан
+            let reader = TrackedReader {
+                inner: File::open(&entry.pth)?.take(entry.meta.len()),
+                tracker,
+            };
+            builder.append_data(&mut header, &entry.name, reader)?;
+        } else {
+            header.set_size(0);
+            builder.append_data(&mut header, &entry.name, io::empty())?;
+        }
+    }
+
+    Ok(builder.into_inner()?)
+}
+
+fn write_zip<W: Write + Seek>(
+    writer: W,
+    entries: &[Entry],
+    level: u32,
+    tracker: &mut Tracker,
+) -> Result<W> {
+    let mut zip = ZipWriter::new(writer);
+    let options = match level {
+        0 => SimpleFileOptions::default().compression_method(CompressionMethod::Stored),
+        level => SimpleFileOptions::default()
+            .compression_method(CompressionMethod::Deflated)
+            .compression_level(Some(level as i64)),
+    };
+
+    for entry in entries {
+        tracker.start(entry)?;
+        let name = entry.name.to_string_lossy();
+        let options = options
+            .unix_permissions(entry.meta.permissions().mode() & 0o7777)
+            .last_modified_time(zip_time(entry.meta.modified()?))
+            .large_file(entry.meta.len() >= u32::MAX as u64);
+
+        if entry.meta.is_symlink() {
+            let target = fs::read_link(&entry.pth)?;
+            zip.add_symlink(name, target.to_string_lossy(), options)?;
+        } else if entry.meta.is_dir() {
+            zip.add_directory(name, options)?;
+        } else if entry.meta.is_file() {
+            zip.start_file(name, options)?;
+            // Файл мог вырасти после подсчёта общего размера
+            let mut reader = TrackedReader {
+                inner: File::open(&entry.pth)?.take(entry.meta.len()),
+                tracker,
+            };
+            io::copy(&mut reader, &mut zip)?;
+        }
+        // Устройства, сокеты и каналы zip хранить не умеет
+    }
+
+    Ok(zip.finish()?)
+}
+
+/// Parameters of the created archive
+#[derive(Debug, Clone)]
+pub struct Archive {
+    pub format: Format,
+    /// Compression level (see `Format::levels()`)
+    pub level: u32,
+    /// Don't add hidden files (including hidden files in the directories)
+    pub exclude_hidden: bool,
+}
+
+impl Archive {
+    /// Create archive `dest` with `files` (and all directories contents).
+    /// The `progress` callback returns `false` to cancel the creation. It is
+    /// an error if `dest` already exists
+    pub fn create<P: AsRef<Path>>(
+        &self,
+        files: &[PathBuf],
+        dest: P,
+        progress: &mut dyn FnMut(&Progress) -> bool,
+    ) -> Result<()> {
+        let dest = dest.as_ref();
+        self.format.check_level(self.level)?;
+
+        let mut entries = Vec::new();
+        for pth in files {
+            let name = PathBuf::from(pth.file_name().unwrap_or(pth.as_os_str()));
+            if self.exclude_hidden && name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            collect(pth, name, self.exclude_hidden, &mut entries)?;
+        }
+        if entries.is_empty() {
+            return Err(anyhow!("Nothing to archive"));
+        }
+
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(dest)
+            .map_err(|why| anyhow!("Failed to create '{}': {}", dest.display(), why))?;
+
+        let mut tracker = Tracker {
+            progress: Progress {
+                total: entries
+                    .iter()
+                    .filter(|entry| entry.meta.is_file())
+                    .map(|entry| entry.meta.len())
+                    .sum(),
+                ..Default::default()
+            },
+            callback: progress,
+            reported: 0,
+        };
+        let rslt = self.write(BufWriter::new(file), &entries, &mut tracker);
+
+        match rslt {
+            Ok(_) => {
+                tracker.progress.file = PathBuf::new();
+                let _ = tracker.report();
+                Ok(())
+            }
+            Err(why) => {
+                // Не оставляем недописанный архив
+                let _ = fs::remove_file(dest);
+                Err(anyhow!("Failed to create '{}': {}", dest.display(), why))
+            }
+        }
+    }
+
+    fn write(
+        &self,
+        writer: BufWriter<File>,
+        entries: &[Entry],
+        tracker: &mut Tracker,
+    ) -> Result<()> {
+        let mut writer = match self.format {
+            Format::Tar => write_tar(writer, entries, tracker)?,
+            Format::TarGz => {
+                let encoder = GzEncoder::new(writer, Compression::new(self.level));
+                write_tar(encoder, entries, tracker)?.finish()?
+            }
+            Format::TarZst => {
+                let encoder = zstd::Encoder::new(writer, self.level as i32)?;
+                write_tar(encoder, entries, tracker)?.finish()?
+            }
+            Format::Zip => write_zip(writer, entries, self.level, tracker)?,
+        };
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MemberKind {
+    File,
+    Dir,
+    Symlink(PathBuf),
+
+    /// Hard links, devices, etc. (they aren't extracted)
+    Other,
+}
+
+/// File stored in the archive
+#[derive(Debug, Clone)]
+pub struct Member {
+    /// Path in the archive (without `/` and `./` at the beginning)
+    pub name: PathBuf,
+    pub kind: MemberKind,
+    pub size: u64,
+    /// Permission bits (0 if the archive doesn't store them)
+    pub mode: u32,
+    pub mtime: Option<SystemTime>,
+}
+
+/// Get the path of the member. Paths going outside of the archive (with
+/// `..`) are ignored
+fn member_name(pth: &Path) -> Option<PathBuf> {
+    let mut name = PathBuf::new();
+    for component in pth.components() {
+        match component {
+            Component::Normal(part) => name.push(part),
+            Component::CurDir | Component::RootDir => {}
+            _ => return None,
+        }
+    }
+    Some(name).filter(|name| !name.as_os_str().is_empty())
+}
+
+fn read_error<E: Display>(file: &Path, why: E) -> anyhow::Error {
+    anyhow!("Failed to read '{}': {}", file.display(), why)
+}
+
+/// Convert time from the zip format
+fn system_time(time: DateTime) -> Option<SystemTime> {
+    let days = days_from_civil(time.year().into(), time.month().into(), time.day().into());
+    let secs = days * 86400
+        + i64::from(time.hour()) * 3600
+        + i64::from(time.minute()) * 60
+        + i64::from(time.second());
+    Some(SystemTime::UNIX_EPOCH + Duration::from_secs(secs.try_into().ok()?))
+}
+
+/// Call `f` for every member of the archive with the reader of its contents.
+/// Stops when `f` returns `false`
+fn for_each(file: &Path, f: &mut dyn FnMut(Member, &mut dyn Read) -> Result<bool>) -> Result<()> {
+    let format = Format::from_path(file)
+        .ok_or_else(|| anyhow!("'{}' is not a supported archive", file.display()))?;
+    let reader = BufReader::new(File::open(file).map_err(|why| read_error(file, why))?);
+    let reader: Box<dyn Read> = match format {
+        Format::Zip => return for_each_zip(file, reader, f),
+        Format::Tar => Box::new(reader),
+        Format::TarGz => Box::new(GzDecoder::new(reader)),
+        Format::TarZst => {
+            Box::new(zstd::Decoder::with_buffer(reader).map_err(|why| read_error(file, why))?)
+        }
+    };
+
+    let mut archive = tar::Archive::new(reader);
+    for entry in archive.entries().map_err(|why| read_error(file, why))? {
+        let mut entry = entry.map_err(|why| read_error(file, why))?;
+        let Some(name) = entry.path().ok().and_then(|pth| member_name(&pth)) else {
+            continue;
+        };
+        let header = entry.header();
+        let kind = match header.entry_type() {
+            EntryType::Regular | EntryType::Continuous => MemberKind::File,
+            EntryType::Directory => MemberKind::Dir,
+            EntryType::Symlink => MemberKind::Symlink(
+                header
+                    .link_name()
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default()
+                    .to_path_buf(),
+            ),
+            _ => MemberKind::Other,
+        };
+        let member = Member {
+            name,
+            kind,
+            size: entry.size(),
+            mode: header.mode().unwrap_or(0) & 0o7777,
+            mtime: header
+                .mtime()
+                .ok()
+                .map(|mtime| SystemTime::UNIX_EPOCH + Duration::from_secs(mtime)),
+        };
+        if !f(member, &mut entry)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+fn for_each_zip<R: Read + Seek>(
+    file: &Path,
+    reader: R,
+    f: &mut dyn FnMut(Member, &mut dyn Read) -> Result<bool>,
+) -> Result<()> {
+    let mut zip = ZipArchive::new(reader).map_err(|why| read_error(file, why))?;
+
+    for idx in 0..zip.len() {
+        let mut zip_file = zip.by_index(idx).map_err(|why| read_error(file, why))?;
+        let Some(name) = zip_file.enclosed_name().and_then(|pth| member_name(&pth)) else {
+            continue;
+        };
+        let kind = if zip_file.is_dir() {
+            MemberKind::Dir
+        } else if zip_file.is_symlink() {
+            // Путь, на который указывает ссылка, хранится как её содержимое
+            let mut target = String::new();
+            zip_file
+                .read_to_string(&mut target)
+                .map_err(|why| read_error(file, why))?;
+            MemberKind::Symlink(PathBuf::from(target))
+        } else {
+            MemberKind::File
+        };
+        let member = Member {
+            name,
+            size: if kind == MemberKind::File {
+                zip_file.size()
+            } else {
+                0
+            },
+            kind,
+            mode: zip_file.unix_mode().unwrap_or(0) & 0o7777,
+            mtime: zip_file.last_modified().and_then(system_time),
+        };
+        if !f(member, &mut zip_file)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Get all members of the archive. Directories which aren't stored in the
+/// archive, but contain the stored files, are added too
+pub fn list<P: AsRef<Path>>(file: P) -> Result<Vec<Member>> {
+    let mut members = BTreeMap::new();
+    for_each(file.as_ref(), &mut |member, _| {
+        members.insert(member.name.clone(), member);
+        Ok(true)
+    })?;
+
+    let dirs = members
+        .keys()
+        .flat_map(|name| name.ancestors().skip(1))
+        .filter(|dir| !dir.as_os_str().is_empty())
+        .map(|dir| dir.to_path_buf())
+        .collect::<Vec<_>>();
+    for dir in dirs {
+        members.entry(dir.clone()).or_insert(Member {
+            name: dir,
+            kind: MemberKind::Dir,
+            size: 0,
+            mode: 0,
+            mtime: None,
+        });
+    }
+
+    Ok(members.into_values().collect())
+}
+
+/// Read contents of the archive member (not more than `limit` bytes)
+pub fn read_member<P: AsRef<Path>>(file: P, name: &Path, limit: u64) -> Result<Vec<u8>> {
+    let mut data = None;
+    for_each(file.as_ref(), &mut |member, reader| {
+        if member.name != name {
+            return Ok(true);
+        }
+        let mut buf = Vec::new();
+        reader.take(limit).read_to_end(&mut buf)?;
+        data = Some(buf);
+        Ok(false)
+    })?;
+
+    data.ok_or_else(|| anyhow!("'{}' not found in the archive", name.display()))
+}
+
+/// Writes the extracted files. Existing files are never overwritten
+struct Extractor<'a> {
+    dest: &'a Path,
+    /// Directories created while extracting
+    created: HashSet<PathBuf>,
+    /// Permissions of the directories (they are set after extracting, so
+    /// read-only directories can be filled)
+    dir_modes: Vec<(PathBuf, u32)>,
+}
+
+impl Extractor<'_> {
+    fn create_dir(&mut self, pth: &Path) -> Result<()> {
+        if pth == self.dest || self.created.contains(pth) {
+            return Ok(());
+        }
+        if let Some(parent) = pth.parent() {
+            self.create_dir(parent)?;
+        }
+        // Только `create_dir()`: существующая директория (или ссылка из
+        // архива) в пути - ошибка, иначе файлы могли бы попасть за пределы
+        // `dest`
+        fs::create_dir(pth)
+            .map_err(|why| anyhow!("Failed to create '{}': {}", pth.display(), why))?;
+        self.created.insert(pth.to_path_buf());
+        Ok(())
+    }
+
+    fn extract(&mut self, member: &Member, pth: &Path, reader: &mut dyn Read) -> Result<()> {
+        if let Some(parent) = pth.parent() {
+            self.create_dir(parent)?;
+        }
+        let err = |why: io::Error| anyhow!("Failed to create '{}': {}", pth.display(), why);
+
+        match &member.kind {
+            MemberKind::Dir => {
+                self.create_dir(pth)?;
+                let mode = if member.mode == 0 { 0o755 } else { member.mode };
+                self.dir_modes.push((pth.to_path_buf(), mode));
+            }
+            MemberKind::File => {
+                let mode = if member.mode == 0 { 0o644 } else { member.mode };
+                let mut file = OpenOptions::new()
+                    .write(true)
+                    .create_new(true)
+                    .mode(mode)
+                    .open(pth)
+                    .map_err(err)?;
+                io::copy(reader, &mut file).map_err(err)?;
+                fs::set_permissions(pth, Permissions::from_mode(mode)).map_err(err)?;
+                if let Some(mtime) = member.mtime {
+                    file.set_modified(mtime).map_err(err)?;
+                }
+            }
+            MemberKind::Symlink(target) => symlink(target, pth).map_err(err)?,
+            MemberKind::Other => {}
+        }
+        Ok(())
+    }
+}
+
+/// Extract members `names` (with all their contents) to the `dest`
+/// directory. Empty `names` means the whole archive
+pub fn extract<P: AsRef<Path>, Q: AsRef<Path>>(file: P, names: &[PathBuf], dest: Q) -> Result<()> {
+    let (file, dest) = (file.as_ref(), dest.as_ref());
+    fs::create_dir_all(dest)
+        .map_err(|why| anyhow!("Failed to create '{}': {}", dest.display(), why))?;
+
+    let mut extractor = Extractor {
+        dest,
+        created: HashSet::new(),
+        dir_modes: Vec::new(),
+    };
+    for_each(file, &mut |member, reader| {
+        // `a/b` распаковывается в `dest/b`
+        let rel = if names.is_empty() {
+            Some(member.name.as_path())
+        } else {
+            names
+                .iter()
+                .find(|name| member.name.starts_with(name))
+                .and_then(|name| member.name.strip_prefix(name.parent()?).ok())
+        };
+        if let Some(rel) = rel {
+            extractor.extract(&member, &dest.join(rel), reader)?;
+        }
+        Ok(true)
+    })?;
+
+    for (pth, mode) in extractor.dir_modes.iter().rev() {
+        fs::set_permissions(pth, Permissions::from_mode(*mode))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use f_core::test_utils::TempDir;
+    use std::collections::BTreeMap;
+
+    /// Names and contents of the files in tar archive
+    fn tar_files<R: Read>(reader: R) -> BTreeMap<String, String> {
+        let mut archive = tar::Archive::new(reader);
+        let mut files = BTreeMap::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let name = entry.path().unwrap().to_string_lossy().to_string();
+            let mut data = String::new();
+            entry.read_to_string(&mut data).unwrap();
+            files.insert(name, data);
+        }
+        files
+    }
+
+    #[test]
+    fn create_test() {
+        let dir = TempDir::new("archive");
+        fs::create_dir_all(dir.join("src/sub")).unwrap();
+        fs::write(dir.join("src/a"), "a").unwrap();
+        fs::write(dir.join("src/.hidden"), "h").unwrap();
+        fs::write(dir.join("src/sub/b"), "b".repeat(3 << 20)).unwrap();
+        let files = [dir.join("src")];
+
+        let expected = BTreeMap::from([
+            ("src".to_string(), String::new()),
+            ("src/a".to_string(), "a".to_string()),
+            ("src/sub".to_string(), String::new()),
+            ("src/sub/b".to_string(), "b".repeat(3 << 20)),
+        ]);
+
+        for format in Format::ALL {
+            let archive = Archive {
+                format,
+                level: format.default_level(),
+                exclude_hidden: true,
+            };
+            let dest = dir.join(format!("out{}", format.ext()));
+            let mut last = Progress::default();
+            archive
+                .create(&files, &dest, &mut |progress| {
+                    last = progress.clone();
+                    true
+                })
+                .unwrap();
+            assert_eq!((last.done, last.total), (3 << 20 | 1, 3 << 20 | 1));
+            assert!(archive.create(&files, &dest, &mut |_| true).is_err());
+
+            let file = File::open(&dest).unwrap();
+            match format {
+                Format::Tar => assert_eq!(tar_files(file), expected),
+                Format::TarGz => {
+                    assert_eq!(tar_files(flate2::read::GzDecoder::new(file)), expected)
+                }
+                Format::TarZst => {
+                    assert_eq!(tar_files(zstd::Decoder::new(file).unwrap()), expected)
+                }
+                Format::Zip => {
+                    let mut zip = zip::ZipArchive::new(file).unwrap();
+                    assert_eq!(zip.len(), 4);
+                    assert!(zip.by_name("src/.hidden").is_err());
+                    let mut data = String::new();
+                    zip.by_name("src/a")
+                        .unwrap()
+                        .read_to_string(&mut data)
+                        .unwrap();
+                    assert_eq!(data, "a");
+                }
+            }
+        }
+
+        // Отмена удаляет недописанный архив
+        let archive = Archive {
+            format: Format::Tar,
+            level: 0,
+            exclude_hidden: false,
+        };
+        let dest = dir.join("cancelled.tar");
+        assert!(archive.create(&files, &dest, &mut |_| false).is_err());
+        assert!(!dest.exists());
+        assert!(archive
+            .create(&files, &dest, &mut |progress| progress.done == 0)
+            .is_err());
+        assert!(!dest.exists());
+
+        let archive = Archive {
+            format: Format::TarZst,
+            level: 0,
+            exclude_hidden: false,
+        };
+        assert!(archive
+            .create(&files, dir.join("x.tar.zst"), &mut |_| true)
+            .is_err());
+
+        assert_eq!(
+            zip_time(SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1738411261)),
+            DateTime::from_date_and_time(2025, 2, 1, 12, 1, 1).unwrap()
+        );
+    }
+
+    #[test]
+    fn read_test() {
+        let dir = TempDir::new("unarchive");
+        fs::create_dir_all(dir.join("src/sub")).unwrap();
+        fs::write(dir.join("src/a"), "a").unwrap();
+        fs::write(dir.join("src/sub/b"), "b").unwrap();
+        fs::set_permissions(dir.join("src/sub/b"), Permissions::from_mode(0o600)).unwrap();
+        symlink("a", dir.join("src/link")).unwrap();
+
+        assert_eq!(Format::from_path("a.TGZ"), Some(Format::TarGz));
+        assert_eq!(Format::from_path("a.tar.zst"), Some(Format::TarZst));
+        assert_eq!(Format::from_path("a.gz"), None);
+        assert_eq!(member_name(Path::new("./a/b")), Some(PathBuf::from("a/b")));
+        assert_eq!(member_name(Path::new("../a")), None);
+
+        for format in [Format::TarGz, Format::Zip] {
+            let file = dir.join(format!("out{}", format.ext()));
+            let archive = Archive {
+                format,
+                level: format.default_level(),
+                exclude_hidden: false,
+            };
+            archive
+                .create(&[dir.join("src")], &file, &mut |_| true)
+                .unwrap();
+
+            let members = list(&file).unwrap();
+            let names = members
+                .iter()
+                .map(|member| member.name.to_string_lossy())
+                .collect::<Vec<_>>();
+            assert_eq!(names, ["src", "src/a", "src/link", "src/sub", "src/sub/b"]);
+            assert_eq!(members[2].kind, MemberKind::Symlink(PathBuf::from("a")));
+            assert_eq!(members[4].mode, 0o600);
+            assert_eq!(
+                read_member(&file, Path::new("src/sub/b"), 10).unwrap(),
+                b"b"
+            );
+            assert!(read_member(&file, Path::new("src/x"), 10).is_err());
+
+            let dest = dir.join(format!("dest{}", format.ext()));
+            extract(&file, &[PathBuf::from("src/sub")], &dest).unwrap();
+            assert_eq!(fs::read_to_string(dest.join("sub/b")).unwrap(), "b");
+            assert!(!dest.join("a").exists());
+            // Существующие файлы не перезаписываются
+            assert!(extract(&file, &[PathBuf::from("src/sub")], &dest).is_err());
+
+            extract(&file, &[], &dest).unwrap();
+            assert_eq!(fs::read_to_string(dest.join("src/link")).unwrap(), "a");
+            let meta = fs::metadata(dest.join("src/sub/b")).unwrap();
+            assert_eq!(meta.permissions().mode() & 0o7777, 0o600);
+        }
+    }
+}
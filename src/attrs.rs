@@ -0,0 +1,125 @@
+//! Changing permissions and ownership of files (like `chmod` and `chown`)
+
+use anyhow::{anyhow, Result};
+use std::{
+    fs::{self, Permissions},
+    os::unix::fs::{lchown, PermissionsExt},
+    path::Path,
+};
+
+/// Changes applied to the files
+#[derive(Debug, Clone)]
+pub struct Attrs {
+    /// Permission bits (`None` if they aren't changed)
+    pub mode: Option<u32>,
+
+    /// Masks applied to the `mode` of the files and directories (e.g. `0666`
+    /// for files removes the executable bits from them, but not from the
+    /// directories)
+    pub file_mask: u32,
+    pub dir_mask: u32,
+
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+
+    /// Apply changes to all the directory contents
+    pub recursive: bool,
+}
+
+impl Default for Attrs {
+    fn default() -> Self {
+        Self {
+            mode: None,
+            file_mask: 0o7777,
+            dir_mask: 0o7777,
+            uid: None,
+            gid: None,
+            recursive: false,
+        }
+    }
+}
+
+impl Attrs {
+    fn apply_one(&self, pth: &Path, is_dir: bool, is_link: bool) -> Result<()> {
+        if self.uid.is_some() || self.gid.is_some() {
+            lchown(pth, self.uid, self.gid)
+                .map_err(|why| anyhow!("Failed to change owner of '{}': {}", pth.display(), why))?;
+        }
+
+        // Права символических ссылок в Linux не используются, а chmod(2)
+        // изменил бы права файла, на который указывает ссылка
+        if let (Some(mode), false) = (self.mode, is_link) {
+            let mask = if is_dir {
+                self.dir_mask
+            } else {
+                self.file_mask
+            };
+            fs::set_permissions(pth, Permissions::from_mode(mode & mask)).map_err(|why| {
+                anyhow!(
+                    "Failed to change permissions of '{}': {}",
+                    pth.display(),
+                    why
+                )
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Apply changes to the file (and its contents if `recursive`). Errors
+    /// are collected for every file
+    pub fn apply<P: AsRef<Path>>(&self, pth: P, errors: &mut Vec<anyhow::Error>) {
+        let pth = pth.as_ref();
+        let meta = match pth.symlink_metadata() {
+            Ok(meta) => meta,
+            Err(why) => {
+                errors.push(anyhow!("Failed to open '{}': {}", pth.display(), why));
+                return;
+            }
+        };
+
+        if let Err(why) = self.apply_one(pth, meta.is_dir(), meta.is_symlink()) {
+            errors.push(why);
+        }
+
+        if self.recursive && meta.is_dir() {
+            match fs::read_dir(pth) {
+                Ok(entries) => {
+                    for entry in entries.filter_map(|entry| entry.ok()) {
+                        self.apply(entry.path(), errors);
+                    }
+                }
+                Err(why) => errors.push(anyhow!("Failed to read '{}': {}", pth.display(), why)),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use f_core::test_utils::TempDir;
+    use std::os::unix::fs::MetadataExt;
+
+    #[test]
+    fn apply_test() {
+        let dir = TempDir::new("attrs");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/a"), "").unwrap();
+        let mode = |pth: &str| fs::metadata(dir.join(pth)).unwrap().mode() & 0o7777;
+
+        let mut errors = Vec::new();
+        Attrs {
+            mode: Some(0o750),
+            file_mask: 0o666,
+            recursive: true,
+            ..Default::default()
+        }
+        .apply(&dir, &mut errors);
+        assert!(errors.is_empty());
+        assert_eq!(mode("sub"), 0o750);
+        assert_eq!(mode("sub/a"), 0o640);
+
+        Attrs::default().apply(dir.join("missing"), &mut errors);
+        assert_eq!(errors.len(), 1);
+    }
+}
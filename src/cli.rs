@@ -0,0 +1,245 @@
+//! Command-line arguments
+
+use crate::consts::{PROG_NAME, PROG_VER};
+use crate::find::{civil_from_days, parse_time};
+use crate::vfs;
+use f_core::conf::Conf;
+use f_core::consts::MASTER_CONF;
+use f_core::recycle_bin::{RecycleBin, RecycleBinEntry};
+use f_core::traits::Toml;
+use f_core::utils::get_home;
+
+use anyhow::{anyhow, Result};
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use std::{fs, path::Path, path::PathBuf};
+
+#[derive(Debug, Parser)]
+#[command(name = PROG_NAME, version = PROG_VER, about = "Simple console file manager")]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Directory (or archive) opened in the active tab
+    #[arg(default_value = ".")]
+    pub dir: PathBuf,
+
+    /// Show hidden files
+    #[arg(short = 'a', long)]
+    pub show_hidden: bool,
+
+    /// File with the color scheme
+    #[arg(long, value_name = "FILE")]
+    pub theme: Option<PathBuf>,
+
+    /// Configuration file [default: ~/.config/f/master.conf]
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// Open the directory of the file with this file selected
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["dir", "two_panel"])]
+    pub select: Option<PathBuf>,
+
+    /// Open two panels with these directories
+    #[arg(long, num_args = 2, value_names = ["LEFT", "RIGHT"], conflicts_with = "dir")]
+    pub two_panel: Option<Vec<PathBuf>>,
+
+    /// Write the last viewed directory to the FILE on exit (for the shell
+    /// wrappers from `contrib/`)
+    #[arg(long, value_name = "FILE")]
+    pub choosedir: Option<String>,
+
+    /// Choose files and write their paths to the OUT (`-` - stdout)
+    #[arg(long, value_name = "OUT", conflicts_with = "choose_dir")]
+    pub choose_files: Option<String>,
+
+    /// Choose directories and write their paths to the OUT (`-` - stdout)
+    #[arg(long, value_name = "OUT")]
+    pub choose_dir: Option<String>,
+
+    /// Separate the chosen paths by NUL instead of newlines
+    #[arg(long)]
+    pub print0: bool,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Manage the recycle bin without the TUI
+    #[command(subcommand)]
+    Trash(Trash),
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Trash {
+    /// Move files to the recycle bin
+    Put {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// List deleted files (ID, time of deleting and original path)
+    List {
+        /// Print the list in JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Move the file back to its original path
+    Restore {
+        /// ID (or its unique beginning) or original path of the file
+        file: String,
+    },
+    /// Remove all deleted files permanently
+    Empty {
+        /// Remove only the files deleted earlier (e.g. `30d`, `2w` or
+        /// `2025-02-01`)
+        #[arg(long, value_name = "TIME")]
+        older_than: Option<String>,
+    },
+    /// Remove the deleted file permanently
+    Rm {
+        /// ID (or its unique beginning) of the file
+        id: String,
+    },
+}
+
+/// Entry of `f trash list --json`
+#[derive(Serialize)]
+struct JsonEntry<'a> {
+    id: &'a str,
+    path: &'a str,
+    deleted_at: u64,
+}
+
+impl Trash {
+    pub fn run(&self) -> Result<()> {
+        match self {
+            Self::Put { paths } => {
+                for pth in paths {
+                    pth.symlink_metadata()
+                        .map_err(|why| anyhow!("Failed to open {}: {}", pth.display(), why))?;
+                    RecycleBinEntry::new(pth).safe_delete()?;
+                }
+            }
+            Self::List { json } => {
+                let rbin = RecycleBin::load()?;
+                if *json {
+                    let entries = rbin
+                        .entryes
+                        .iter()
+                        .map(|entry| JsonEntry {
+                            id: &entry.deleted_name,
+                            path: &entry.orig_path,
+                            deleted_at: entry.deleted_at,
+                        })
+                        .collect::<Vec<_>>();
+                    println!("{}", serde_json::to_string_pretty(&entries)?);
+                } else {
+                    for entry in &rbin.entryes {
+                        println!(
+                            "{}  {}  {}",
+                            entry.deleted_name,
+                            format_time(entry.deleted_at),
+                            entry.orig_path
+                        );
+                    }
+                }
+            }
+            Self::Restore { file } => {
+                let entry = RecycleBin::update(|rbin| rbin.restore(file))?;
+                println!("Restored {}", entry.orig_path);
+            }
+            Self::Empty { older_than } => {
+                let older_than = older_than.as_deref().map(parse_time).transpose()?;
+                let removed = RecycleBin::update(|rbin| rbin.empty(older_than))?;
+                println!("Removed {removed} files");
+            }
+            Self::Rm { id } => {
+                let entry = RecycleBin::update(|rbin| rbin.remove(id))?;
+                println!("Removed {}", entry.orig_path);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Time as `YYYY-MM-DD HH:MM` (UTC)
+fn format_time(secs: u64) -> String {
+    if secs == 0 {
+        return "-".repeat(16);
+    }
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let secs = secs % 86400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}",
+        secs / 3600,
+        secs % 3600 / 60
+    )
+}
+
+impl Cli {
+    /// Read the configuration file. Default file may be absent
+    pub fn conf(&self) -> Result<Conf> {
+        match &self.config {
+            Some(pth) => Conf::parse(pth),
+            None => {
+                let pth = get_home().join(MASTER_CONF);
+                match pth.exists() {
+                    true => Conf::parse(pth),
+                    false => Ok(Conf::default()),
+                }
+            }
+        }
+    }
+}
+
+/// Check that the start directory exists (before the terminal is
+/// initialized). Archives and remote directories are opened by the TUI
+pub fn check_dir(pth: &Path) -> Result<()> {
+    // Удалённые директории проверяются при подключении
+    if vfs::archive_file(pth).is_some() || vfs::is_url(pth) {
+        return Ok(());
+    }
+    let metadata =
+        fs::metadata(pth).map_err(|why| anyhow!("Failed to open {}: {}", pth.display(), why))?;
+    match metadata.is_dir() {
+        true => Ok(()),
+        false => Err(anyhow!("{} is not a directory", pth.display())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cli_test() {
+        let cli = Cli::try_parse_from(["f", "-a", "--two-panel", "/a", "/b", "--print0"]).unwrap();
+        assert!(cli.show_hidden && cli.print0);
+        assert_eq!(cli.dir, Path::new("."));
+        assert_eq!(
+            cli.two_panel.unwrap(),
+            [PathBuf::from("/a"), PathBuf::from("/b")]
+        );
+
+        assert!(Cli::try_parse_from(["f", "--select", "/a/b", "/c"]).is_err());
+        assert!(Cli::try_parse_from(["f", "--two-panel", "/a"]).is_err());
+        assert!(Cli::try_parse_from(["f", "--two-panel", "/a", "/b", "/c"]).is_err());
+        assert!(Cli::try_parse_from(["f", "/c", "--two-panel", "/a", "/b"]).is_err());
+        assert!(Cli::try_parse_from(["f", "--choose-files", "-", "--choose-dir", "-"]).is_err());
+
+        let cli = Cli::try_parse_from(["f", "trash", "empty", "--older-than", "30d"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Trash(Trash::Empty {
+                older_than: Some(_)
+            }))
+        ));
+        assert!(Cli::try_parse_from(["f", "trash", "put"]).is_err());
+        assert_eq!(format_time(20120 * 86400 + 3661), "2025-02-01 01:01");
+
+        assert!(check_dir(Path::new("/")).is_ok());
+        assert!(check_dir(Path::new("/nonexistent")).is_err());
+        assert!(check_dir(Path::new("/etc/passwd")).is_err());
+        assert!(check_dir(Path::new("sftp://host/tmp")).is_ok());
+    }
+}
@@ -0,0 +1,255 @@
+//! Internal clipboard for copying and moving files between directories
+
+use anyhow::{anyhow, Result};
+use std::{
+    collections::VecDeque,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use f_core::{
+    utils::{real_path, remove_path},
+    FileEntry,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ClipboardMode {
+    /// Files will be copied on paste
+    Copy,
+
+    /// Files will be moved on paste
+    Cut,
+}
+
+/// Files yanked (copied) or cut by user
+#[derive(Debug, Clone)]
+pub struct Clipboard {
+    pub mode: ClipboardMode,
+    pub files: Vec<FileEntry>,
+}
+
+/// What to do if the pasted file already exists in the destination directory
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Resolution {
+    /// Remove the existing file
+    Overwrite,
+
+    /// Don't paste this file
+    Skip,
+
+    /// Paste file with the new name (`name_1.ext`, `name_2.ext`, ...)
+    Rename,
+}
+
+/// Get the non-existing path by adding the numeric suffix to the file name
+pub fn unique_path<P: AsRef<Path>>(pth: P) -> PathBuf {
+    let pth = pth.as_ref();
+    let stem = pth.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = pth.extension().map(|ext| ext.to_string_lossy());
+
+    (1..)
+        .map(|n| {
+            let mut name = OsString::from(format!("{stem}_{n}"));
+            if let Some(ext) = &ext {
+                name.push(format!(".{ext}"));
+            }
+            pth.with_file_name(name)
+        })
+        .find(|pth| pth.symlink_metadata().is_err())
+        .unwrap()
+}
+
+/// Pasting files from the clipboard. Pasting is paused when the file already
+/// exists until user chooses the `Resolution`
+pub struct Paste {
+    mode: ClipboardMode,
+    files: VecDeque<FileEntry>,
+    dest_dir: PathBuf,
+
+    /// Resolution chosen for all the remaining conflicts
+    for_all: Option<Resolution>,
+    pub errors: Vec<anyhow::Error>,
+}
+
+impl Paste {
+    pub fn new<P: AsRef<Path>>(clipboard: &Clipboard, dest_dir: P) -> Self {
+        Self {
+            mode: clipboard.mode,
+            files: clipboard.files.iter().cloned().collect(),
+            dest_dir: dest_dir.as_ref().to_path_buf(),
+            for_all: None,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Path of the pasted file in the destination directory
+    fn dest(&self, file: &FileEntry) -> PathBuf {
+        self.dest_dir
+            .join(file.path.file_name().unwrap_or(file.file_name.as_os_str()))
+    }
+
+    fn paste_file(&self, file: &FileEntry, dest: &Path) -> Result<()> {
+        match self.mode {
+            ClipboardMode::Copy => file.copy_to(dest),
+            ClipboardMode::Cut => file.move_to(dest),
+        }
+        .map(|_| ())
+    }
+
+    fn resolve_file(&self, file: &FileEntry, dest: &Path, res: Resolution) -> Result<()> {
+        match res {
+            Resolution::Skip => Ok(()),
+            Resolution::Rename => self.paste_file(file, &unique_path(dest)),
+            Resolution::Overwrite => self.overwrite(file, dest),
+        }
+    }
+
+    /// Replace the existing `dest` by the pasted file. The file is pasted
+    /// with the temporary name first, so `dest` is removed only when its
+    /// replacement exists
+    fn overwrite(&self, file: &FileEntry, dest: &Path) -> Result<()> {
+        let real = |pth: &Path| {
+            real_path(pth).map_err(|why| anyhow!("Failed to access '{}': {}", pth.display(), why))
+        };
+        let src = real(&file.path)?;
+        let real_dest = real(dest)?;
+        if src.starts_with(&real_dest) {
+            return Err(anyhow!(
+                "Failed to overwrite '{}' by itself or its contents",
+                dest.display()
+            ));
+        }
+
+        let name = dest.file_name().unwrap_or_default().to_string_lossy();
+        let tmp = unique_path(dest.with_file_name(format!(".f-paste-{name}")));
+        let backup = unique_path(dest.with_file_name(format!(".f-replaced-{name}")));
+        self.paste_file(file, &tmp)?;
+
+        if let Err(why) = fs::rename(dest, &backup) {
+            // Перемещённый файл нельзя удалять: это единственная его копия
+            if self.mode == ClipboardMode::Copy {
+                let _ = remove_path(&tmp);
+            }
+            return Err(anyhow!(
+                "Failed to overwrite '{}': {} (pasted file is left in '{}')",
+                dest.display(),
+                why,
+                tmp.display()
+            ));
+        }
+        if let Err(why) = fs::rename(&tmp, dest) {
+            // Существующий файл возвращается на место
+            let _ = fs::rename(&backup, dest);
+            return Err(anyhow!(
+                "Failed to overwrite '{}': {} (pasted file is left in '{}')",
+                dest.display(),
+                why,
+                tmp.display()
+            ));
+        }
+        remove_path(&backup)
+    }
+
+    /// Paste files until the conflict. Returns the path of the existing file
+    /// or `None` if all files are pasted
+    pub fn run(&mut self) -> Option<PathBuf> {
+        while let Some(file) = self.files.front().cloned() {
+            let dest = self.dest(&file);
+            let rslt = if dest.symlink_metadata().is_err() {
+                self.paste_file(&file, &dest)
+            } else if let Some(res) = self.for_all {
+                self.resolve_file(&file, &dest, res)
+            } else {
+                return Some(dest);
+            };
+
+            if let Err(why) = rslt {
+                self.errors.push(why);
+            }
+            self.files.pop_front();
+        }
+        None
+    }
+
+    /// Resolve the current conflict and continue pasting
+    pub fn resolve(&mut self, res: Resolution, for_all: bool) -> Option<PathBuf> {
+        if for_all {
+            self.for_all = Some(res);
+        }
+        if let Some(file) = self.files.pop_front() {
+            let dest = self.dest(&file);
+            if let Err(why) = self.resolve_file(&file, &dest, res) {
+                self.errors.push(why);
+            }
+        }
+        self.run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use f_core::test_utils::TempDir;
+
+    #[test]
+    fn unique_path_test() {
+        let dir = TempDir::new("unique_path");
+        fs::write(dir.join("a.txt"), "").unwrap();
+        fs::write(dir.join("a_1.txt"), "").unwrap();
+
+        assert_eq!(unique_path(dir.join("a.txt")), dir.join("a_2.txt"));
+        assert_eq!(unique_path(dir.join("b")), dir.join("b_1"));
+    }
+
+    #[test]
+    fn overwrite_test() {
+        let dir = TempDir::new("overwrite");
+        fs::create_dir_all(dir.join("a/a")).unwrap();
+        fs::create_dir_all(dir.join("dest/b")).unwrap();
+        fs::write(dir.join("a/a/file"), "new").unwrap();
+        fs::write(dir.join("b"), "new").unwrap();
+        fs::write(dir.join("dest/b/file"), "old").unwrap();
+
+        let entries = |paths: &[&str]| {
+            paths
+                .iter()
+                .map(|pth| {
+                    let entry = fs::read_dir(dir.join(pth).parent().unwrap())
+                        .unwrap()
+                        .filter_map(|entry| entry.ok())
+                        .find(|entry| entry.path() == dir.join(pth))
+                        .unwrap();
+                    FileEntry::from_dir_entry(&entry).unwrap()
+                })
+                .collect::<Vec<_>>()
+        };
+
+        // Файл не может заменить себя или директорию, в которой он находится
+        let clipboard = Clipboard {
+            mode: ClipboardMode::Copy,
+            files: entries(&["a/a"]),
+        };
+        let mut paste = Paste::new(&clipboard, dir.join("a/a/.."));
+        assert_eq!(paste.run(), Some(dir.join("a/a/../a")));
+        assert_eq!(paste.resolve(Resolution::Overwrite, false), None);
+        let mut paste = Paste::new(&clipboard, &dir);
+        assert_eq!(paste.run(), Some(dir.join("a")));
+        assert_eq!(paste.resolve(Resolution::Overwrite, false), None);
+        assert_eq!(paste.errors.len(), 1);
+        assert_eq!(fs::read_to_string(dir.join("a/a/file")).unwrap(), "new");
+
+        // Директория заменяется файлом
+        let clipboard = Clipboard {
+            mode: ClipboardMode::Cut,
+            files: entries(&["b"]),
+        };
+        let mut paste = Paste::new(&clipboard, dir.join("dest"));
+        assert_eq!(paste.run(), Some(dir.join("dest/b")));
+        assert_eq!(paste.resolve(Resolution::Overwrite, false), None);
+        assert!(paste.errors.is_empty());
+        assert_eq!(fs::read_to_string(dir.join("dest/b")).unwrap(), "new");
+        assert!(!dir.join("b").exists());
+        assert_eq!(fs::read_dir(dir.join("dest")).unwrap().count(), 1);
+    }
+}
@@ -13,10 +13,9 @@ pub const PROG_REPOSITORY: &str = "https://github.com/mskrasnov/f";
 /**********************************************************
  *                       Some pathes                      *
  **********************************************************/
-pub const CONF_DIR: &str = ".config/f/";
-pub const MASTER_CONF: &str = ".config/f/master.conf";
+// Пути, используемые библиотекой, находятся в `f_core::consts`
 pub const HISTORY_FILE: &str = ".cofnig/f/history";
-pub const UNAME_FILE: &str = "/proc/version";
-pub const OS_RELEASE_FILE: &str = "/etc/os-release";
-pub const RECYCLE_BIN_DIR: &str = ".local/share/f_bin/";
-pub const RECYCLE_BIN_META: &str = ".local/share/f_bin/f_bin.toml";
+pub const SESSION_FILE: &str = ".config/f/session.toml";
+pub const OPENERS_FILE: &str = ".config/f/openers.toml";
+pub const PASSWD_FILE: &str = "/etc/passwd";
+pub const GROUP_FILE: &str = "/etc/group";
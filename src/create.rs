@@ -0,0 +1,336 @@
+//! Creating new files (like `touch` and `dd`), directories (like
+//! `mkdir -p`) and links (like `ln`)
+
+use anyhow::{anyhow, Result};
+use std::{
+    fs::{self, DirBuilder, File, FileTimes, OpenOptions, Permissions},
+    io::{self, BufWriter, Read, Write},
+    os::unix::fs::{symlink, DirBuilderExt, MetadataExt, OpenOptionsExt, PermissionsExt},
+    path::{Component, Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Contents of the created file
+#[derive(Debug, Clone, PartialEq)]
+pub enum Fill {
+    /// File without allocated blocks (reading returns zeros)
+    Sparse,
+
+    Zeros,
+
+    /// Random bytes from `/dev/urandom`
+    Random,
+
+    /// Repeated bytes
+    Pattern(Vec<u8>),
+}
+
+/// Parse octal permission bits (e.g. `644` or `0755`)
+pub fn parse_mode(s: &str) -> Result<u32> {
+    match u32::from_str_radix(s.trim(), 8) {
+        Ok(mode) if mode <= 0o7777 => Ok(mode),
+        _ => Err(anyhow!("Wrong permissions: '{s}' (use e.g. '644')")),
+    }
+}
+
+/// Check the path of the created file or directory. Returns warnings about
+/// the names which are valid but inconvenient to use in the shell
+pub fn check_path(s: &str) -> Result<Vec<String>> {
+    if s.is_empty() {
+        return Err(anyhow!("Name is empty"));
+    }
+    if s.contains('\0') {
+        return Err(anyhow!("Name must not contain NUL character"));
+    }
+
+    let mut warnings = Vec::new();
+    for name in s.split('/') {
+        if name.starts_with('-') {
+            warnings.push(format!(
+                "'{name}' starts with '-' (may be treated as an option)"
+            ));
+        }
+        if name.ends_with(char::is_whitespace) {
+            warnings.push(format!("'{name}' ends with space"));
+        }
+    }
+    Ok(warnings)
+}
+
+/// Create directory with all its parents (like `mkdir -p`). The last
+/// directory gets the `mode` permissions. It is an error if the directory
+/// already exists
+pub fn create_dir<P: AsRef<Path>>(pth: P, mode: u32) -> Result<()> {
+    let pth = pth.as_ref();
+    if pth.symlink_metadata().is_ok() {
+        return Err(anyhow!("'{}' already exists", pth.display()));
+    }
+
+    // Промежуточные директории создаются с правами по умолчанию, иначе при
+    // `mode` без права записи в них нельзя было бы создать следующую
+    pth.parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| DirBuilder::new().mode(mode).create(pth))
+        .and_then(|_| fs::set_permissions(pth, Permissions::from_mode(mode)))
+        .map_err(|why| anyhow!("Failed to create '{}': {}", pth.display(), why))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LinkKind {
+    /// Symbolic link with the absolute path of the target
+    Absolute,
+
+    /// Symbolic link with the path of the target relative to the link
+    Relative,
+
+    Hard,
+}
+
+/// Get absolute path without resolving the last component (so the path of
+/// the symbolic link isn't replaced by the path of its target)
+fn absolute_path(pth: &Path) -> Result<PathBuf> {
+    let parent = match pth.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let parent = fs::canonicalize(parent)
+        .map_err(|why| anyhow!("Failed to open '{}': {}", parent.display(), why))?;
+
+    Ok(match pth.file_name() {
+        Some(name) => parent.join(name),
+        None => parent,
+    })
+}
+
+/// Get path to `target` relative to the `dir` directory. Both paths must be
+/// absolute
+pub fn relative_path(dir: &Path, target: &Path) -> PathBuf {
+    let dir = dir.components().collect::<Vec<_>>();
+    let target = target.components().collect::<Vec<_>>();
+    let common = dir.iter().zip(&target).take_while(|(a, b)| a == b).count();
+
+    let mut pth = PathBuf::new();
+    for _ in common..dir.len() {
+        pth.push(Component::ParentDir);
+    }
+    for component in &target[common..] {
+        pth.push(component);
+    }
+    if pth.as_os_str().is_empty() {
+        pth.push(Component::CurDir);
+    }
+    pth
+}
+
+/// Create link to the `target` file at the `link` path
+pub fn create_link(target: &Path, link: &Path, kind: LinkKind) -> Result<()> {
+    if link.symlink_metadata().is_ok() {
+        return Err(anyhow!("'{}' already exists", link.display()));
+    }
+    let target = absolute_path(target)?;
+    let link = absolute_path(link)?;
+
+    let rslt = match kind {
+        LinkKind::Absolute => symlink(&target, &link),
+        LinkKind::Relative => symlink(
+            relative_path(link.parent().unwrap_or(Path::new("/")), &target),
+            &link,
+        ),
+        LinkKind::Hard => {
+            let meta = target.symlink_metadata()?;
+            if meta.is_dir() {
+                return Err(anyhow!("Hard links to directories are not allowed"));
+            }
+            let link_dir = link.parent().unwrap_or(Path::new("/"));
+            // Жёсткая ссылка возможна только в пределах одной ФС; проверяем
+            // заранее, чтобы вместо EXDEV показать понятную ошибку
+            if fs::metadata(link_dir)?.dev() != meta.dev() {
+                return Err(anyhow!(
+                    "Cannot create hard link: '{}' and '{}' are on different filesystems",
+                    target.display(),
+                    link_dir.display()
+                ));
+            }
+            fs::hard_link(&target, &link)
+        }
+    };
+
+    rslt.map_err(|why| anyhow!("Failed to create link '{}': {}", link.display(), why))
+}
+
+#[derive(Debug, Clone)]
+pub struct NewFile {
+    /// Size in bytes
+    pub size: u64,
+    pub fill: Fill,
+
+    /// Access and modification times (current time if `None`)
+    pub atime: Option<SystemTime>,
+    pub mtime: Option<SystemTime>,
+
+    /// Permission bits
+    pub mode: u32,
+}
+
+impl Default for NewFile {
+    fn default() -> Self {
+        Self {
+            size: 0,
+            fill: Fill::Sparse,
+            atime: None,
+            mtime: None,
+            mode: 0o644,
+        }
+    }
+}
+
+impl NewFile {
+    fn write_contents(&self, file: &mut File) -> Result<()> {
+        let pattern = match &self.fill {
+            Fill::Sparse => {
+                file.set_len(self.size)?;
+                return Ok(());
+            }
+            Fill::Random => {
+                let mut random = File::open("/dev/urandom")?.take(self.size);
+                io::copy(&mut random, file)?;
+                return Ok(());
+            }
+            Fill::Zeros => vec![0],
+            Fill::Pattern(pattern) if pattern.is_empty() => {
+                return Err(anyhow!("Fill pattern is empty"));
+            }
+            Fill::Pattern(pattern) => pattern.clone(),
+        };
+
+        // Записываем блоками по ~64 KBytes, а не по одному шаблону
+        let chunk = pattern
+            .iter()
+            .cycle()
+            .take(pattern.len() * (65536 / pattern.len()).max(1))
+            .copied()
+            .collect::<Vec<_>>();
+        let mut writer = BufWriter::new(file);
+        let mut left = self.size;
+        while left > 0 {
+            let len = left.min(chunk.len() as u64);
+            writer.write_all(&chunk[..len as usize])?;
+            left -= len;
+        }
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Create the file. It is an error if the file already exists
+    pub fn create<P: AsRef<Path>>(&self, pth: P) -> Result<()> {
+        let pth = pth.as_ref();
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(self.mode)
+            .open(pth)
+            .map_err(|why| anyhow!("Failed to create '{}': {}", pth.display(), why))?;
+
+        let rslt = self.write_contents(&mut file).and_then(|_| {
+            // Права задаём явно, так как `mode()` учитывает umask
+            fs::set_permissions(pth, Permissions::from_mode(self.mode))?;
+
+            let mut times = FileTimes::new();
+            if let Some(atime) = self.atime {
+                times = times.set_accessed(atime);
+            }
+            if let Some(mtime) = self.mtime {
+                times = times.set_modified(mtime);
+            }
+            file.set_times(times)?;
+            Ok(())
+        });
+
+        if let Err(why) = rslt {
+            // Не оставляем после себя недописанный файл
+            let _ = fs::remove_file(pth);
+            return Err(anyhow!("Failed to create '{}': {}", pth.display(), why));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use f_core::test_utils::TempDir;
+    use std::{os::unix::fs::MetadataExt, time::Duration};
+
+    #[test]
+    fn create_file_test() {
+        let dir = TempDir::new("create");
+        let mtime = SystemTime::UNIX_EPOCH + Duration::from_secs(86400);
+
+        let new_file = NewFile {
+            size: 70000,
+            fill: Fill::Pattern(b"abc".to_vec()),
+            mtime: Some(mtime),
+            mode: 0o600,
+            ..Default::default()
+        };
+        new_file.create(dir.join("a")).unwrap();
+        assert!(new_file.create(dir.join("a")).is_err());
+
+        let data = fs::read(dir.join("a")).unwrap();
+        assert_eq!(data.len(), 70000);
+        assert_eq!(&data[65535..65541], b"abcabc");
+        let meta = fs::metadata(dir.join("a")).unwrap();
+        assert_eq!(meta.mode() & 0o7777, 0o600);
+        assert_eq!(meta.modified().unwrap(), mtime);
+
+        assert_eq!(parse_mode("0755").unwrap(), 0o755);
+        assert!(parse_mode("999").is_err());
+
+        create_dir(dir.join("b/c/d"), 0o700).unwrap();
+        let meta = fs::metadata(dir.join("b/c/d")).unwrap();
+        assert!(meta.is_dir());
+        assert_eq!(meta.mode() & 0o7777, 0o700);
+        assert!(create_dir(dir.join("b/c"), 0o755).is_err());
+    }
+
+    #[test]
+    fn link_test() {
+        assert_eq!(
+            relative_path(Path::new("/a/b/c"), Path::new("/a/d/e")),
+            PathBuf::from("../../d/e")
+        );
+        assert_eq!(
+            relative_path(Path::new("/a"), Path::new("/a/b")),
+            PathBuf::from("b")
+        );
+        assert_eq!(
+            relative_path(Path::new("/a"), Path::new("/a")),
+            PathBuf::from(".")
+        );
+
+        let dir = TempDir::new("link");
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        fs::write(dir.join("a"), "a").unwrap();
+
+        create_link(&dir.join("a"), &dir.join("sub/rel"), LinkKind::Relative).unwrap();
+        assert_eq!(
+            fs::read_link(dir.join("sub/rel")).unwrap(),
+            PathBuf::from("../a")
+        );
+        create_link(&dir.join("a"), &dir.join("abs"), LinkKind::Absolute).unwrap();
+        assert_eq!(fs::read_link(dir.join("abs")).unwrap(), dir.join("a"));
+        create_link(&dir.join("a"), &dir.join("hard"), LinkKind::Hard).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("hard")).unwrap(), "a");
+        assert!(create_link(&dir.join("a"), &dir.join("hard"), LinkKind::Hard).is_err());
+        assert!(create_link(&dir.join("sub"), &dir.join("hard_dir"), LinkKind::Hard).is_err());
+    }
+
+    #[test]
+    fn check_path_test() {
+        assert!(check_path("").is_err());
+        assert!(check_path("a\0b").is_err());
+        assert!(check_path("a/b/c").unwrap().is_empty());
+        assert_eq!(check_path("-a/b /c").unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,484 @@
+//! Search files by name, size, modification time, type, owner and permissions
+//!
+//! ## Query syntax
+//!
+//! Query is a list of terms separated by spaces. File is found only if it
+//! matches all the terms. Values with spaces are enclosed in double quotes
+//! (`name:"my file*"`, `\"` and `\\` are escapes inside quotes).
+//!
+//! | Term                  | Meaning                                                 |
+//! |-----------------------|---------------------------------------------------------|
+//! | `name:*.log`, `*.log` | file name matches the pattern (`*` and `?` are allowed) |
+//! | `size>500M`           | file is larger than 500 MBytes (`K`, `M`, `G`, `T`)     |
+//! | `size<1K`             | file is smaller than 1 KBytes                           |
+//! | `newer:30d`           | modified during the last 30 days (`m`, `h`, `d`, `w`)   |
+//! | `newer:2025-02-01`    | modified after the specified date                       |
+//! | `older:30d`           | modified earlier than 30 days ago                       |
+//! | `type:f`              | `f` file, `x` executable, `d` dir, `l` link, `s` special |
+//! | `owner:root`          | owner name or UID                                       |
+//! | `group:wheel`         | group name or GID                                       |
+//! | `perm:644`            | permission bits are exactly `644`                       |
+//! | `perm:-002`           | all bits of the mask are set (e.g. world-writable)      |
+//! | `perm:/111`           | any bit of the mask is set                              |
+//! | `depth:3`             | don't descend deeper than 3 levels                      |
+
+use anyhow::{anyhow, Result};
+use std::{
+    ffi::OsString,
+    fs::{self, Metadata},
+    os::unix::fs::MetadataExt,
+    path::Path,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+use crate::users;
+use f_core::{utils::glob_match, FileEntry};
+
+/// File type filter (`type:` term)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TypeFilter {
+    File,
+    Executable,
+    Directory,
+    Link,
+    Special,
+}
+
+/// Permission bits filter (`perm:` term)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PermMask {
+    /// Permission bits are exactly equal to the mask
+    Exact(u32),
+
+    /// All bits of the mask are set
+    All(u32),
+
+    /// Any bit of the mask is set
+    Any(u32),
+}
+
+impl PermMask {
+    pub fn matches(&self, mode: u32) -> bool {
+        let mode = mode & 0o7777;
+        match *self {
+            Self::Exact(mask) => mode == mask,
+            Self::All(mask) => mode & mask == mask,
+            Self::Any(mask) => mode & mask != 0,
+        }
+    }
+}
+
+/// Search query
+#[derive(Debug, Clone, Default)]
+pub struct Query {
+    /// File name pattern
+    pub name: Option<String>,
+
+    /// File is larger than this size (in bytes)
+    pub larger: Option<u64>,
+
+    /// File is smaller than this size (in bytes)
+    pub smaller: Option<u64>,
+
+    /// File was modified after this time
+    pub newer: Option<SystemTime>,
+
+    /// File was modified before this time
+    pub older: Option<SystemTime>,
+
+    pub file_type: Option<TypeFilter>,
+    pub owner: Option<u32>,
+    pub group: Option<u32>,
+    pub perm: Option<PermMask>,
+
+    /// Max depth of nested directories (`1` - only the root directory)
+    pub max_depth: Option<usize>,
+}
+
+impl Query {
+    /// Check if the file matches this query
+    pub fn matches(&self, file_name: &str, meta: &Metadata) -> bool {
+        let ftype = meta.file_type();
+
+        if let Some(pattern) = &self.name {
+            if !glob_match(pattern, file_name) {
+                return false;
+            }
+        }
+        if self.larger.is_some_and(|size| meta.len() <= size)
+            || self.smaller.is_some_and(|size| meta.len() >= size)
+        {
+            return false;
+        }
+        if let Ok(mtime) = meta.modified() {
+            if self.newer.is_some_and(|time| mtime < time)
+                || self.older.is_some_and(|time| mtime > time)
+            {
+                return false;
+            }
+        }
+        if let Some(file_type) = self.file_type {
+            let is_type = match file_type {
+                TypeFilter::File => ftype.is_file(),
+                TypeFilter::Executable => ftype.is_file() && meta.mode() & 0o111 != 0,
+                TypeFilter::Directory => ftype.is_dir(),
+                TypeFilter::Link => ftype.is_symlink(),
+                TypeFilter::Special => !ftype.is_file() && !ftype.is_dir() && !ftype.is_symlink(),
+            };
+            if !is_type {
+                return false;
+            }
+        }
+        if self.owner.is_some_and(|uid| meta.uid() != uid)
+            || self.group.is_some_and(|gid| meta.gid() != gid)
+        {
+            return false;
+        }
+        if self.perm.is_some_and(|perm| !perm.matches(meta.mode())) {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Parse size with optional `K`, `M`, `G` or `T` suffix
+pub fn parse_size(s: &str) -> Result<u64> {
+    let s = s.trim_end_matches(['b', 'B']);
+    let (num, mult) = match s.chars().last() {
+        Some('k' | 'K') => (&s[..s.len() - 1], 1_u64 << 10),
+        Some('m' | 'M') => (&s[..s.len() - 1], 1 << 20),
+        Some('g' | 'G') => (&s[..s.len() - 1], 1 << 30),
+        Some('t' | 'T') => (&s[..s.len() - 1], 1 << 40),
+        _ => (s, 1),
+    };
+    let num = num
+        .parse::<f64>()
+        .map_err(|_| anyhow!("Wrong size: '{s}'"))?;
+
+    Ok((num * mult as f64) as u64)
+}
+
+/// Days since 1970-01-01 for the specified date of the proleptic Gregorian
+/// calendar
+pub fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (m + if m > 2 { -3 } else { 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+
+    era * 146097 + doe - 719468
+}
+
+/// Date of the proleptic Gregorian calendar (year, month, day) for the days
+/// since 1970-01-01 (inverse of `days_from_civil`)
+pub fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+
+    (yoe + era * 400 + i64::from(month <= 2), month, day)
+}
+
+/// Parse relative (`30d`, `2w`, `12h`, `15m`) or absolute time
+/// (`YYYY-MM-DD`, `YYYY-MM-DDTHH:MM[:SS]` or the same with space, UTC)
+pub fn parse_time(s: &str) -> Result<SystemTime> {
+    let err = || anyhow!("Wrong time: '{s}' (use e.g. '30d' or '2025-02-01')");
+
+    let (date, time) = s.split_once([' ', 'T']).unwrap_or((s, ""));
+    let date = date.split('-').collect::<Vec<_>>();
+    if date.len() == 3 {
+        let date = date
+            .iter()
+            .map(|n| n.parse::<i64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| err())?;
+        let time = time
+            .split(':')
+            .filter(|n| !n.is_empty())
+            .map(|n| n.parse::<i64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| err())?;
+        if time.len() > 3
+            || time
+                .iter()
+                .zip([24, 60, 60])
+                .any(|(n, max)| !(0..max).contains(n))
+        {
+            return Err(err());
+        }
+
+        // Несуществующие даты (например, 2025-02-30) не совпадают с датой,
+        // восстановленной из количества дней
+        let (year, month, day) = (date[0], date[1], date[2]);
+        if !(1..=9999).contains(&year) || !(1..=12).contains(&month) || day < 1 {
+            return Err(err());
+        }
+        let days = days_from_civil(year, month, day);
+        if civil_from_days(days) != (year, month, day) {
+            return Err(err());
+        }
+
+        let secs = time
+            .iter()
+            .zip([3600, 60, 1])
+            .try_fold(
+                days.checked_mul(86400).ok_or_else(err)?,
+                |secs, (n, mult)| secs.checked_add(n * mult),
+            )
+            .ok_or_else(err)?;
+        let secs = u64::try_from(secs).map_err(|_| err())?;
+
+        return Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(secs));
+    }
+
+    let mult = match s.chars().last() {
+        Some('m') => 60,
+        Some('h') => 60 * 60,
+        Some('d') => 60 * 60 * 24,
+        Some('w') => 60 * 60 * 24 * 7,
+        _ => return Err(err()),
+    };
+    let num = s[..s.len() - 1].parse::<u64>().map_err(|_| err())?;
+    let secs = num.checked_mul(mult).ok_or_else(err)?;
+
+    SystemTime::now()
+        .checked_sub(Duration::from_secs(secs))
+        .ok_or_else(err)
+}
+
+/// Split the query into terms separated by spaces. Parts of the term in
+/// double quotes may contain spaces
+pub fn split_terms(s: &str) -> Result<Vec<String>> {
+    let mut terms = Vec::new();
+    let mut term = None::<String>;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let term = term.get_or_insert_with(String::new);
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c) => term.push(c),
+                            None => return Err(anyhow!("Unterminated quote in '{s}'")),
+                        },
+                        Some(c) => term.push(c),
+                        None => return Err(anyhow!("Unterminated quote in '{s}'")),
+                    }
+                }
+            }
+            c if c.is_whitespace() => terms.extend(term.take()),
+            c => term.get_or_insert_with(String::new).push(c),
+        }
+    }
+    terms.extend(term);
+
+    Ok(terms)
+}
+
+/// Quote the value of the term if it contains spaces or quotes (see
+/// `split_terms`)
+pub fn quote(s: &str) -> String {
+    if !s.contains(|c: char| c.is_whitespace() || c == '"') {
+        return s.to_string();
+    }
+    let escaped = s.replace('\\', "\\\\").replace('"', "\\\"");
+
+    format!("\"{escaped}\"")
+}
+
+fn parse_perm(s: &str) -> Result<PermMask> {
+    let mask =
+        |m: &str| u32::from_str_radix(m, 8).map_err(|_| anyhow!("Wrong permission mask: '{s}'"));
+
+    if let Some(m) = s.strip_prefix('-') {
+        Ok(PermMask::All(mask(m)?))
+    } else if let Some(m) = s.strip_prefix('/') {
+        Ok(PermMask::Any(mask(m)?))
+    } else {
+        Ok(PermMask::Exact(mask(s)?))
+    }
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let mut query = Self::default();
+
+        for term in split_terms(s)? {
+            if let Some(size) = term.strip_prefix("size>") {
+                query.larger = Some(parse_size(size)?);
+            } else if let Some(size) = term.strip_prefix("size<") {
+                query.smaller = Some(parse_size(size)?);
+            } else if let Some((key, val)) = term.split_once(':') {
+                match key {
+                    "name" => query.name = Some(val.to_string()),
+                    "newer" => query.newer = Some(parse_time(val)?),
+                    "older" => query.older = Some(parse_time(val)?),
+                    "type" => {
+                        query.file_type = Some(match val {
+                            "f" => TypeFilter::File,
+                            "x" => TypeFilter::Executable,
+                            "d" => TypeFilter::Directory,
+                            "l" => TypeFilter::Link,
+                            "s" => TypeFilter::Special,
+                            _ => return Err(anyhow!("Wrong file type: '{val}'")),
+                        })
+                    }
+                    "owner" => {
+                        query.owner =
+                            Some(users::uid(val).ok_or_else(|| anyhow!("Unknown user: '{val}'"))?)
+                    }
+                    "group" => {
+                        query.group =
+                            Some(users::gid(val).ok_or_else(|| anyhow!("Unknown group: '{val}'"))?)
+                    }
+                    "perm" => query.perm = Some(parse_perm(val)?),
+                    "depth" => {
+                        query.max_depth = Some(
+                            val.parse()
+                                .map_err(|_| anyhow!("Wrong search depth: '{val}'"))?,
+                        )
+                    }
+                    _ => return Err(anyhow!("Unknown search term: '{term}'")),
+                }
+            } else {
+                query.name = Some(term);
+            }
+        }
+
+        Ok(query)
+    }
+}
+
+fn walk(
+    dir: &Path,
+    root: &Path,
+    depth: usize,
+    query: &Query,
+    show_hidden: bool,
+    found: &mut Vec<FileEntry>,
+) -> Result<()> {
+    for entry in fs::read_dir(dir)?.filter_map(|entry| entry.ok()) {
+        let Ok(mut file) = FileEntry::from_dir_entry(&entry) else {
+            continue;
+        };
+        if file.is_hidden && !show_hidden {
+            continue;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+
+        if query.matches(&file.file_name.to_string_lossy(), &meta) {
+            let rel_path = file.path.strip_prefix(root).unwrap_or(&file.path);
+            file.file_name = OsString::from(rel_path);
+            found.push(file);
+        }
+
+        if meta.is_dir() && query.max_depth.is_none_or(|max| depth < max) {
+            // Ошибки чтения вложенных директорий (например, нет прав доступа)
+            // не должны прерывать весь поиск
+            let _ = walk(&entry.path(), root, depth + 1, query, show_hidden, found);
+        }
+    }
+
+    Ok(())
+}
+
+/// Find all files in `root` and its subdirectories matching the query
+pub fn find<P: AsRef<Path>>(root: P, query: &Query, show_hidden: bool) -> Result<Vec<FileEntry>> {
+    let root = root.as_ref();
+    let mut found = Vec::new();
+
+    walk(root, root, 1, query, show_hidden, &mut found)
+        .map_err(|err| anyhow!("Failed to search in '{}': {}", root.display(), err))?;
+    found.sort_by_key(|key| key.file_name.clone());
+
+    Ok(found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_query_test() {
+        let query = "*.log size>500M perm:-002 type:f depth:2"
+            .parse::<Query>()
+            .unwrap();
+
+        assert_eq!(query.name.as_deref(), Some("*.log"));
+        assert_eq!(query.larger, Some(500 * 1024 * 1024));
+        assert_eq!(query.perm, Some(PermMask::All(0o002)));
+        assert_eq!(query.file_type, Some(TypeFilter::File));
+        assert_eq!(query.max_depth, Some(2));
+
+        let query = r#"name:"my file*" newer:"2025-02-01 12:30""#.parse::<Query>().unwrap();
+        assert_eq!(query.name.as_deref(), Some("my file*"));
+        assert!(query.newer.is_some());
+
+        assert!("size>lots".parse::<Query>().is_err());
+        assert!("name:\"a".parse::<Query>().is_err());
+        assert!("color:red".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn perm_mask_test() {
+        assert!(PermMask::All(0o002).matches(0o100666));
+        assert!(!PermMask::All(0o002).matches(0o100644));
+        assert!(PermMask::Any(0o111).matches(0o100744));
+        assert!(PermMask::Exact(0o644).matches(0o100644));
+    }
+
+    #[test]
+    fn date_test() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2025, 2, 1), 20120);
+        assert_eq!(civil_from_days(20120), (2025, 2, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+
+        let day = SystemTime::UNIX_EPOCH + Duration::from_secs(20120 * 86400);
+        assert_eq!(parse_time("2025-02-01").unwrap(), day);
+        assert_eq!(
+            parse_time("2025-02-01 12:30").unwrap(),
+            day + Duration::from_secs(12 * 3600 + 30 * 60)
+        );
+        assert_eq!(
+            parse_time("2025-02-01T00:00:05").unwrap(),
+            day + Duration::from_secs(5)
+        );
+        assert!(parse_time("2025-02-01 1:2:3:4").is_err());
+        assert!(parse_time("2025-13-45 99:99").is_err());
+        assert!(parse_time("2025-02-29").is_err());
+        assert!(parse_time("2024-02-29 23:59:59").is_ok());
+        assert!(parse_time("99999999999999999-01-01").is_err());
+        assert!(parse_time("99999999999999999w").is_err());
+        assert!(parse_time("2025-02-01 -1:00").is_err());
+    }
+
+    #[test]
+    fn split_terms_test() {
+        assert_eq!(split_terms("  a  b ").unwrap(), ["a", "b"]);
+        assert_eq!(
+            split_terms(r#"name:"a b" "c\"d""#).unwrap(),
+            ["name:a b", "c\"d"]
+        );
+        assert!(split_terms(r#"name:"a"#).is_err());
+
+        for val in ["a b", r#"a"b"#, r"a\ b", "plain"] {
+            assert_eq!(split_terms(&quote(val)).unwrap(), [val]);
+        }
+    }
+}
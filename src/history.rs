@@ -1,2 +0,0 @@
-//! Write navigation history
-
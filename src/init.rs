@@ -1,10 +1,10 @@
 //! Create directories after first start
 
-use crate::{
+use anyhow::{anyhow, Result};
+use f_core::{
     consts::{CONF_DIR, RECYCLE_BIN_DIR},
     utils::get_home,
 };
-use anyhow::{anyhow, Result};
 use std::fs;
 
 const DIRS: [&str; 2] = [CONF_DIR, RECYCLE_BIN_DIR];
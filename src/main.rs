@@ -7,39 +7,122 @@
  * License   : MIT (see '/LICENSE' file in this repository)                  *
  *****************************************************************************/
 
+pub mod archive;
+pub mod attrs;
+pub mod cli;
+pub mod clipboard;
 pub mod consts;
-pub mod ftype; // complete
-pub mod history;
+pub mod create;
+pub mod find;
 pub mod init; // complete
-pub mod os_release;
-pub mod recycle_bin; // complete
-pub mod traits; // complete
+pub mod opener;
+pub mod rename;
+pub mod session;
+pub mod shell;
+pub mod system_clipboard;
 pub mod tui;
-pub mod utils; // complete
-pub mod conf;
+pub mod users;
+pub mod vfs;
 
 // NOTE: experimental module
 pub mod tui_new;
 
-use anyhow::Result;
-use ftype::*;
-use std::env;
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use cli::{Cli, Command};
+use std::{
+    fs,
+    io::{self, Write},
+    os::unix::ffi::OsStrExt,
+    path::PathBuf,
+    process::ExitCode,
+};
+use tui::{Choose, Options};
 
-fn main() -> Result<()> {
+fn main() -> Result<ExitCode> {
+    let cli = Cli::parse();
     init::create_dirs()?;
+    if let Some(Command::Trash(trash)) = &cli.command {
+        trash.run()?;
+        return Ok(ExitCode::SUCCESS);
+    }
 
-    let args = env::args().collect::<Vec<_>>();
-    let binding = ".".to_string();
-    let fpth = args.get(1).unwrap_or(&binding);
+    // Ошибки в аргументах должны выводиться до инициализации терминала
+    let mut options = Options {
+        show_hidden: cli.show_hidden,
+        theme: cli.theme.clone(),
+        conf: cli.conf()?,
+        ..Default::default()
+    };
+    let start_dir = match (&cli.select, &cli.two_panel) {
+        (Some(file), _) => {
+            let file = fs::canonicalize(file)
+                .map_err(|why| anyhow!("Failed to open {}: {}", file.display(), why))?;
+            options.select = file
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string());
+            file.parent().unwrap_or(&file).to_path_buf()
+        }
+        (None, Some(dirs)) => {
+            cli::check_dir(&dirs[1])?;
+            options.second_dir = Some(dirs[1].clone());
+            dirs[0].clone()
+        }
+        (None, None) => cli.dir.clone(),
+    };
+    cli::check_dir(&start_dir)?;
+
+    // Режим выбора файлов для других программ и файл для выбранных путей
+    // (`-` - stdout)
+    let chooser = match (&cli.choose_files, &cli.choose_dir) {
+        (Some(out), _) => Some((Choose::Files, out)),
+        (None, Some(out)) => Some((Choose::Dir, out)),
+        (None, None) => None,
+    };
+    let separator = match cli.print0 {
+        true => b'\0',
+        false => b'\n',
+    };
 
     // Создание экземпляра `tui` до инициализации терминала для того, чтобы
     // гарантировать, что `ratatui` будет проинициализирован только в случае
     // успешного создания экземпляра.
-    let mut tui = tui::F::new(fpth)?;
+    let mut tui = tui::F::new(&start_dir, options)?;
+    if let Some((choose, _)) = chooser {
+        tui = tui.chooser(choose);
+    }
 
-    let mut term = ratatui::init();
+    let mut term = tui::term::init()?;
     let rslt = tui.run(&mut term);
-    ratatui::restore();
+    tui::term::restore();
+    rslt?;
+
+    // Файл, в который при выходе записывается последняя просмотренная
+    // директория (для смены директории оболочки, см. `contrib/`)
+    if let Some(file) = &cli.choosedir {
+        write_paths(file, tui.last_dir().as_slice(), b'\n')?;
+    }
+    if let Some((_, file)) = chooser {
+        // Отмена выбора завершает программу с ошибкой, чтобы скрипты могли
+        // её отличить от выбора
+        if tui.chosen().is_empty() {
+            return Ok(ExitCode::FAILURE);
+        }
+        write_paths(file, tui.chosen(), separator)?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
 
-    rslt
+/// Write paths terminated by the `separator` to the `file` (`-` - stdout)
+fn write_paths(file: &str, paths: &[PathBuf], separator: u8) -> Result<()> {
+    let mut text = Vec::new();
+    for pth in paths {
+        text.extend_from_slice(pth.as_os_str().as_bytes());
+        text.push(separator);
+    }
+    let rslt = match file {
+        "-" => io::stdout().write_all(&text),
+        _ => fs::write(file, text),
+    };
+    rslt.map_err(|why| anyhow!("Failed to write {file}: {why}"))
 }
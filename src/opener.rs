@@ -0,0 +1,315 @@
+//! Opening files with the external programs
+//!
+//! Rules are read from `~/.config/f/openers.toml`:
+//!
+//! ```toml
+//! [[rule]]
+//! ext = ["txt", "md", "rs"]
+//! command = "$EDITOR {}"
+//!
+//! [[rule]]
+//! name = "Video player"
+//! mime = "video/*"
+//! command = "mpv {}"
+//! background = true
+//! ```
+//!
+//! `{}` is replaced by the path of the file (it's appended to the command if
+//! there is no `{}`). Commands are run by `sh`, so the variables are
+//! expanded. Foreground programs get the whole terminal, background ones are
+//! detached from it
+
+use crate::shell::quote;
+use f_core::traits::Toml;
+use f_core::utils::glob_match;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::File,
+    io::Read,
+    os::unix::process::CommandExt,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Rule {
+    /// Name shown in the "Open with" menu (the command by default)
+    pub name: Option<String>,
+
+    /// Pattern of the MIME type (e.g. `image/*`)
+    pub mime: Option<String>,
+    /// Extensions of the file (without dot, case-insensitive)
+    #[serde(default)]
+    pub ext: Vec<String>,
+    /// Pattern of the file name
+    pub glob: Option<String>,
+
+    pub command: String,
+    /// Run the program in the background (for GUI programs)
+    #[serde(default)]
+    pub background: bool,
+}
+
+impl Rule {
+    fn new(mime: Option<&str>, command: &str, background: bool) -> Self {
+        Self {
+            name: None,
+            mime: mime.map(str::to_string),
+            ext: Vec::new(),
+            glob: None,
+            command: command.to_string(),
+            background,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.command)
+    }
+
+    /// All the specified conditions must match. Rule without conditions
+    /// matches any file
+    fn matches(&self, name: &str, mime: &str) -> bool {
+        let ext = name
+            .rsplit_once('.')
+            .map(|(_, ext)| ext)
+            .unwrap_or_default();
+        self.mime
+            .as_ref()
+            .is_none_or(|pattern| glob_match(pattern, mime))
+            && (self.ext.is_empty() || self.ext.iter().any(|e| e.eq_ignore_ascii_case(ext)))
+            && self
+                .glob
+                .as_ref()
+                .is_none_or(|pattern| glob_match(pattern, name))
+    }
+
+    /// Command for `sh -c`
+    pub fn command_line(&self, pth: &Path) -> String {
+        let pth = quote(&pth.to_string_lossy());
+        match self.command.contains("{}") {
+            true => self.command.replace("{}", &pth),
+            false => format!("{} {pth}", self.command),
+        }
+    }
+
+    /// Start the background program. Foreground programs are run by the TUI
+    /// (see `run`)
+    pub fn spawn(&self, pth: &Path) -> Result<()> {
+        // Программа запускается через промежуточный `sh`, который сразу
+        // завершается, поэтому зомби-процессов не остаётся
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(format!("( {} ) &", self.command_line(pth)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .process_group(0)
+            .status()
+            .map_err(|why| anyhow!("Failed to run '{}': {}", self.name(), why))?;
+        match status.success() {
+            true => Ok(()),
+            false => Err(anyhow!("Failed to run '{}'", self.name())),
+        }
+    }
+
+    /// Run the foreground program and wait for it
+    pub fn run(&self, pth: &Path) -> Result<()> {
+        let status = Command::new("sh")
+            .arg("-c")
+            .arg(self.command_line(pth))
+            .status()
+            .map_err(|why| anyhow!("Failed to run '{}': {}", self.name(), why))?;
+        match status.success() {
+            true => Ok(()),
+            false => Err(anyhow!("'{}' exited with {}", self.name(), status)),
+        }
+    }
+}
+
+/// Rules of opening files (the first matching rule is used by default)
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Openers {
+    #[serde(default, rename = "rule")]
+    pub rules: Vec<Rule>,
+}
+
+impl Toml for Openers {}
+
+impl Default for Openers {
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                Rule::new(Some("text/*"), "${VISUAL:-${EDITOR:-vi}} {}", false),
+                Rule::new(None, "xdg-open {}", true),
+            ],
+        }
+    }
+}
+
+impl Openers {
+    /// Rules matching the file
+    pub fn matching(&self, pth: &Path) -> Vec<&Rule> {
+        let name = pth.file_name().unwrap_or_default().to_string_lossy();
+        let mime = mime_type(pth);
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(&name, mime))
+            .collect()
+    }
+}
+
+/// Known extensions and their MIME types
+const EXTENSIONS: &[(&str, &str)] = &[
+    ("txt", "text/plain"),
+    ("md", "text/markdown"),
+    ("log", "text/plain"),
+    ("rs", "text/x-rust"),
+    ("c", "text/x-c"),
+    ("h", "text/x-c"),
+    ("py", "text/x-python"),
+    ("sh", "text/x-shellscript"),
+    ("toml", "text/x-toml"),
+    ("json", "application/json"),
+    ("html", "text/html"),
+    ("htm", "text/html"),
+    ("css", "text/css"),
+    ("csv", "text/csv"),
+    ("xml", "text/xml"),
+    ("png", "image/png"),
+    ("jpg", "image/jpeg"),
+    ("jpeg", "image/jpeg"),
+    ("gif", "image/gif"),
+    ("webp", "image/webp"),
+    ("svg", "image/svg+xml"),
+    ("bmp", "image/bmp"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("flac", "audio/flac"),
+    ("wav", "audio/wav"),
+    ("mp4", "video/mp4"),
+    ("mkv", "video/x-matroska"),
+    ("webm", "video/webm"),
+    ("avi", "video/x-msvideo"),
+    ("mov", "video/quicktime"),
+    ("pdf", "application/pdf"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("tar", "application/x-tar"),
+    ("zst", "application/zstd"),
+    ("iso", "application/x-iso9660-image"),
+];
+
+/// Signatures of the file contents and their MIME types
+const MAGIC: &[(&[u8], &str)] = &[
+    (b"\x89PNG", "image/png"),
+    (b"\xff\xd8\xff", "image/jpeg"),
+    (b"GIF8", "image/gif"),
+    (b"%PDF", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+    (b"\x1f\x8b", "application/gzip"),
+    (b"\x28\xb5\x2f\xfd", "application/zstd"),
+    (b"\x7fELF", "application/x-executable"),
+    (b"ID3", "audio/mpeg"),
+    (b"fLaC", "audio/flac"),
+    (b"OggS", "audio/ogg"),
+    (b"\x1a\x45\xdf\xa3", "video/x-matroska"),
+];
+
+/// Guess the MIME type of the file by its extension or contents
+pub fn mime_type(pth: &Path) -> &'static str {
+    let ext = pth
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    if let Some((_, mime)) = EXTENSIONS.iter().find(|(e, _)| *e == ext) {
+        return mime;
+    }
+
+    let mut head = Vec::new();
+    if File::open(pth)
+        .and_then(|file| file.take(4096).read_to_end(&mut head))
+        .is_err()
+    {
+        return "application/octet-stream";
+    }
+    if let Some((_, mime)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return mime;
+    }
+    // Текст может быть обрезан посередине символа UTF-8
+    let text = match std::str::from_utf8(&head) {
+        Ok(_) => true,
+        Err(why) => why.error_len().is_none(),
+    };
+    match text && !head.contains(&0) {
+        true => "text/plain",
+        false => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use f_core::test_utils::TempDir;
+    use std::fs;
+
+    #[test]
+    fn opener_test() {
+        let dir = TempDir::new("opener");
+        fs::write(dir.join("notes"), "plain text").unwrap();
+        fs::write(dir.join("image"), b"\x89PNG\r\n\x1a\n\0\0").unwrap();
+        fs::write(dir.join("data"), [0, 1, 2, 255]).unwrap();
+
+        assert_eq!(mime_type(&dir.join("notes")), "text/plain");
+        assert_eq!(mime_type(&dir.join("image")), "image/png");
+        assert_eq!(mime_type(&dir.join("data")), "application/octet-stream");
+        assert_eq!(
+            mime_type(Path::new("/nonexistent/film.MKV")),
+            "video/x-matroska"
+        );
+
+        let openers = toml::from_str::<Openers>(
+            r#"
+            [[rule]]
+            ext = ["MD", "txt"]
+            command = "$EDITOR"
+
+            [[rule]]
+            name = "Viewer"
+            mime = "image/*"
+            command = "imv {} &"
+            background = true
+
+            [[rule]]
+            glob = "*.log"
+            mime = "text/*"
+            command = "less"
+            "#,
+        )
+        .unwrap();
+        let names = |pth: &Path| {
+            openers
+                .matching(pth)
+                .iter()
+                .map(|rule| rule.name().to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(names(&dir.join("image")), ["Viewer"]);
+        assert_eq!(names(Path::new("/x/README.md")), ["$EDITOR"]);
+        assert_eq!(names(Path::new("/x/app.log")), ["less"]);
+        assert!(names(&dir.join("data")).is_empty());
+
+        let rule = &openers.rules[0];
+        assert_eq!(
+            rule.command_line(Path::new("/a b/it's")),
+            r"$EDITOR '/a b/it'\''s'"
+        );
+        assert_eq!(
+            openers.rules[1].command_line(Path::new("/x.png")),
+            "imv '/x.png' &"
+        );
+        assert!(Openers::default().matching(Path::new("/x/y.rs")).len() == 2);
+    }
+}
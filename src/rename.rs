@@ -0,0 +1,362 @@
+//! Batch renaming of files
+//!
+//! New names are made by the rules (see `Rename`) or entered by user in the
+//! text editor. Files are renamed in two steps through the temporary names,
+//! so it is possible to swap names (`a` → `b`, `b` → `a`)
+
+use crate::vfs::Vfs;
+
+use anyhow::{anyhow, Result};
+use regex::{Captures, Regex};
+use std::{
+    collections::{HashMap, HashSet},
+    path::PathBuf,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+    Keep,
+    Lower,
+    Upper,
+
+    /// First letter of every word is uppercase
+    Title,
+}
+
+impl Case {
+    fn apply(&self, s: &str) -> String {
+        match self {
+            Self::Keep => s.to_string(),
+            Self::Lower => s.to_lowercase(),
+            Self::Upper => s.to_uppercase(),
+            Self::Title => {
+                let mut title = String::with_capacity(s.len());
+                let mut word_start = true;
+                for c in s.chars() {
+                    if word_start {
+                        title.extend(c.to_uppercase());
+                    } else {
+                        title.extend(c.to_lowercase());
+                    }
+                    word_start = !c.is_alphanumeric();
+                }
+                title
+            }
+        }
+    }
+}
+
+/// Split file name to the stem and extension (with dot). Hidden files like
+/// `.bashrc` have no extension
+fn split_ext(name: &str) -> (&str, &str) {
+    match name.rfind('.') {
+        Some(idx) if idx > 0 => name.split_at(idx),
+        _ => (name, ""),
+    }
+}
+
+/// Rules of making the new file names
+#[derive(Debug, Clone)]
+pub struct Rename {
+    /// Regular expression replaced in the names. If it is `None`, the
+    /// replacement (if not empty) is used as the new name without extension
+    find: Option<Regex>,
+
+    /// Replacement with the capture groups (`$1`, `${name}`; `${1}` if the
+    /// letter or `_` follows it) and counter (`{n}`, `{n:03}`)
+    replace: String,
+
+    /// Case of the name (without extension)
+    case: Case,
+
+    /// New extension (`None` - keep the old one, empty - remove extension)
+    ext: Option<String>,
+
+    /// Counter value for the first file
+    start: usize,
+
+    counter: Regex,
+}
+
+impl Rename {
+    /// Create rules. `ext` is empty for keeping the old extension or `.` for
+    /// removing it
+    pub fn new(find: &str, replace: &str, case: Case, ext: &str, start: usize) -> Result<Self> {
+        let find = match find {
+            "" => None,
+            find => {
+                Some(Regex::new(find).map_err(|why| anyhow!("Wrong regular expression: {why}"))?)
+            }
+        };
+        let ext = match ext.trim_start_matches('.') {
+            "" if ext.is_empty() => None,
+            ext => Some(ext.to_string()),
+        };
+
+        Ok(Self {
+            find,
+            replace: replace.to_string(),
+            case,
+            ext,
+            start,
+            counter: Regex::new(r"\{n(?::(0)?(\d+))?\}").unwrap(),
+        })
+    }
+
+    /// Get the new name of the file with index `idx` in the renamed list
+    pub fn apply(&self, name: &str, idx: usize) -> String {
+        let n = self.start + idx;
+        let replace = self.counter.replace_all(&self.replace, |caps: &Captures| {
+            let width = caps.get(2).map_or(0, |w| w.as_str().parse().unwrap_or(0));
+            if caps.get(1).is_some() {
+                format!("{n:0width$}")
+            } else {
+                format!("{n:width$}")
+            }
+        });
+
+        let name = match &self.find {
+            Some(find) => find.replace_all(name, replace.as_ref()).to_string(),
+            None if replace.is_empty() => name.to_string(),
+            None => format!("{replace}{}", split_ext(name).1),
+        };
+
+        let (stem, ext) = split_ext(&name);
+        let ext = match &self.ext {
+            None => ext.to_string(),
+            Some(new_ext) if new_ext.is_empty() => String::new(),
+            Some(new_ext) => format!(".{new_ext}"),
+        };
+        format!("{}{ext}", self.case.apply(stem))
+    }
+}
+
+/// Check the new names and get the list of renamings (unchanged files are
+/// skipped)
+pub fn plan(fs: &dyn Vfs, files: &[PathBuf], names: &[String]) -> Result<Vec<(PathBuf, PathBuf)>> {
+    if files.len() != names.len() {
+        return Err(anyhow!(
+            "Expected {} names, got {}",
+            files.len(),
+            names.len()
+        ));
+    }
+
+    let mut renames = Vec::new();
+    for (from, name) in files.iter().zip(names) {
+        if name.is_empty() || name == "." || name == ".." || name.contains(['/', '\0']) {
+            return Err(anyhow!("Wrong file name: '{name}'"));
+        }
+        let to = from.with_file_name(name);
+        if &to != from {
+            renames.push((from.clone(), to));
+        }
+    }
+
+    let sources = renames
+        .iter()
+        .map(|(from, _)| from.clone())
+        .collect::<HashSet<_>>();
+    let mut dests = HashMap::new();
+    for (from, to) in &renames {
+        if let Some(other) = dests.insert(to.clone(), from.clone()) {
+            return Err(anyhow!(
+                "'{}' and '{}' can't be both renamed to '{}'",
+                other.display(),
+                from.display(),
+                to.display()
+            ));
+        }
+        // Существующий файл можно занять, только если он сам переименовывается
+        if fs.stat(to).is_ok() && !sources.contains(to) {
+            return Err(anyhow!("'{}' already exists", to.display()));
+        }
+    }
+
+    Ok(renames)
+}
+
+/// Rename files. Returns the errors for every failed file
+pub fn rename_all(fs: &dyn Vfs, renames: &[(PathBuf, PathBuf)]) -> Vec<anyhow::Error> {
+    let mut errors = Vec::new();
+
+    // Сначала все файлы получают временные имена, иначе при обмене именами
+    // (a → b, b → a) первое переименование затёрло бы второй файл. Все
+    // переименования не заменяют существующие файлы
+    let mut temps = Vec::new();
+    for (idx, (from, to)) in renames.iter().enumerate() {
+        let mut n = 0;
+        loop {
+            let tmp = from.with_file_name(format!(".f-rename-{}-{idx}-{n}", std::process::id()));
+            match fs.rename_noreplace(from, &tmp) {
+                Ok(_) => temps.push((from, tmp, to)),
+                // Временное имя уже занято
+                Err(_) if fs.stat(&tmp).is_ok() => {
+                    n += 1;
+                    continue;
+                }
+                Err(why) => errors.push(why),
+            }
+            break;
+        }
+    }
+
+    for (from, tmp, to) in temps {
+        if let Err(why) = fs.rename_noreplace(&tmp, to) {
+            errors.push(why);
+            // Возвращаем исходное имя, чтобы не оставить временное. Его мог
+            // уже занять другой файл, тогда сообщаем, где остался этот
+            if let Err(why) = fs.rename_noreplace(&tmp, from) {
+                errors.push(anyhow!(
+                    "Failed to restore the name of '{}', the file is left in '{}': {}",
+                    from.display(),
+                    tmp.display(),
+                    why
+                ));
+            }
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::{LocalFs, Stat};
+    use f_core::test_utils::TempDir;
+    use f_core::FileEntry;
+    use std::{
+        fs,
+        io::{Read, Write},
+        path::Path,
+    };
+
+    /// Local files where renaming to `fail_to` fails
+    struct FailingFs {
+        fail_to: PathBuf,
+    }
+
+    impl Vfs for FailingFs {
+        fn contains(&self, pth: &Path) -> bool {
+            LocalFs.contains(pth)
+        }
+        fn list(&self, dir: &Path, show_hidden: bool) -> Result<Vec<FileEntry>> {
+            LocalFs.list(dir, show_hidden)
+        }
+        fn canonicalize(&self, pth: &Path) -> Result<PathBuf> {
+            LocalFs.canonicalize(pth)
+        }
+        fn stat(&self, pth: &Path) -> Result<Stat> {
+            LocalFs.stat(pth)
+        }
+        fn open_read(&self, pth: &Path) -> Result<Box<dyn Read>> {
+            LocalFs.open_read(pth)
+        }
+        fn open_write(&self, pth: &Path, mode: u32) -> Result<Box<dyn Write>> {
+            LocalFs.open_write(pth, mode)
+        }
+        fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+            self.rename_noreplace(from, to)
+        }
+        fn rename_noreplace(&self, from: &Path, to: &Path) -> Result<()> {
+            match to == self.fail_to {
+                true => Err(anyhow!("Failed to rename to '{}'", to.display())),
+                false => LocalFs.rename_noreplace(from, to),
+            }
+        }
+        fn remove(&self, pth: &Path) -> Result<()> {
+            LocalFs.remove(pth)
+        }
+        fn mkdir(&self, pth: &Path, mode: u32) -> Result<()> {
+            LocalFs.mkdir(pth, mode)
+        }
+        fn symlink(&self, target: &Path, pth: &Path) -> Result<()> {
+            LocalFs.symlink(target, pth)
+        }
+    }
+
+    #[test]
+    fn apply_test() {
+        let rename = Rename::new(r"IMG_(\d+)", "photo_${1}_{n:03}", Case::Keep, "", 1).unwrap();
+        assert_eq!(rename.apply("IMG_42.JPG", 4), "photo_42_005.JPG");
+
+        let rename = Rename::new("", "file{n}", Case::Upper, "txt", 10).unwrap();
+        assert_eq!(rename.apply("a.log", 0), "FILE10.txt");
+
+        let rename = Rename::new("", "", Case::Title, ".", 1).unwrap();
+        assert_eq!(rename.apply("hello wORLD-foo.tar", 0), "Hello World-Foo");
+        assert_eq!(rename.apply(".bashrc", 0), ".Bashrc");
+
+        assert!(Rename::new("(", "", Case::Keep, "", 1).is_err());
+    }
+
+    #[test]
+    fn rename_all_test() {
+        let dir = TempDir::new("rename");
+        for name in ["a", "b", "c"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+        let files = [dir.join("a"), dir.join("b")];
+
+        let swap = plan(&LocalFs, &files, &["b".to_string(), "a".to_string()]).unwrap();
+        assert!(rename_all(&LocalFs, &swap).is_empty());
+        assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "b");
+        assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "a");
+
+        assert!(plan(&LocalFs, &files, &["c".to_string(), "b".to_string()]).is_err());
+        assert!(plan(&LocalFs, &files, &["d".to_string(), "d".to_string()]).is_err());
+        assert!(plan(&LocalFs, &files, &["x/y".to_string(), "b".to_string()]).is_err());
+        assert!(plan(&LocalFs, &files, &["a".to_string(), "b".to_string()])
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn rename_all_rollback_test() {
+        let dir = TempDir::new("rename_rollback");
+        for name in ["a", "b", "c"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+        let files = [dir.join("a"), dir.join("b"), dir.join("c")];
+        let names = ["b", "c", "a"].map(String::from);
+        let rotate = plan(&LocalFs, &files, &names).unwrap();
+
+        // b → c не удаётся, а имя `b` уже занято файлом `a`: файл `b` не
+        // должен его затереть и остаётся под временным именем
+        let failing = FailingFs {
+            fail_to: dir.join("c"),
+        };
+        let errors = rename_all(&failing, &rotate);
+        assert_eq!(errors.len(), 2);
+        assert!(errors[1].to_string().contains(".f-rename-"));
+        assert_eq!(fs::read_to_string(dir.join("a")).unwrap(), "c");
+        assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "a");
+        assert!(!dir.join("c").exists());
+
+        let left = fs::read_dir(&dir)
+            .unwrap()
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+            .filter(|pth| pth.to_string_lossy().contains(".f-rename-"))
+            .collect::<Vec<_>>();
+        assert_eq!(left.len(), 1);
+        assert_eq!(fs::read_to_string(&left[0]).unwrap(), "b");
+    }
+
+    #[test]
+    fn rename_noreplace_test() {
+        let dir = TempDir::new("noreplace");
+        fs::write(dir.join("a"), "a").unwrap();
+        fs::write(dir.join("b"), "b").unwrap();
+
+        assert!(LocalFs
+            .rename_noreplace(&dir.join("a"), &dir.join("b"))
+            .is_err());
+        assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "b");
+        LocalFs
+            .rename_noreplace(&dir.join("a"), &dir.join("c"))
+            .unwrap();
+        assert_eq!(fs::read_to_string(dir.join("c")).unwrap(), "a");
+    }
+}
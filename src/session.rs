@@ -0,0 +1,92 @@
+//! Open tabs saved between `f` launches
+
+use f_core::{history::History, traits::Toml, utils::SortBy};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Session {
+    /// Index of the active tab
+    pub active_tab: usize,
+    /// Commands entered in the command line (the last one is the newest)
+    #[serde(default)]
+    pub commands: Vec<String>,
+
+    #[serde(rename = "tab", default)]
+    pub tabs: Vec<TabState>,
+}
+
+impl Toml for Session {}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TabState {
+    /// Index of the active panel
+    pub active_panel: usize,
+
+    #[serde(rename = "panel")]
+    pub panels: Vec<PanelState>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PanelState {
+    pub dir: PathBuf,
+
+    /// Name of the file under cursor
+    pub selected: Option<String>,
+
+    #[serde(default)]
+    pub sort: SortBy,
+    #[serde(default)]
+    pub reverse: bool,
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub history: History,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn session_test() {
+        let mut history = History::default();
+        history.push("/b");
+        let session = Session {
+            active_tab: 1,
+            commands: vec!["ls %d".to_string()],
+            tabs: vec![
+                TabState {
+                    active_panel: 0,
+                    panels: vec![PanelState {
+                        dir: PathBuf::from("/a"),
+                        selected: Some("x".to_string()),
+                        sort: SortBy::Size,
+                        reverse: true,
+                        filter: Some("*.rs".to_string()),
+                        history,
+                    }],
+                },
+                TabState {
+                    active_panel: 1,
+                    panels: Vec::new(),
+                },
+            ],
+        };
+        let text = toml::to_string(&session).unwrap();
+        let restored = toml::from_str::<Session>(&text).unwrap();
+        assert_eq!(restored.active_tab, 1);
+        assert_eq!(restored.commands, ["ls %d"]);
+        assert_eq!(restored.tabs.len(), 2);
+        let panel = &restored.tabs[0].panels[0];
+        assert_eq!(panel.dir, PathBuf::from("/a"));
+        assert_eq!(panel.selected.as_deref(), Some("x"));
+        assert_eq!(panel.sort, SortBy::Size);
+        assert!(panel.reverse);
+        assert_eq!(panel.filter.as_deref(), Some("*.rs"));
+        assert_eq!(panel.history.clone().back("/a"), Some(PathBuf::from("/b")));
+
+        // Сессия без команд и вкладок (от старых версий) тоже читается
+        let old = toml::from_str::<Session>("active_tab = 0").unwrap();
+        assert!(old.tabs.is_empty() && old.commands.is_empty());
+    }
+}
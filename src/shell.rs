@@ -0,0 +1,121 @@
+//! Shell commands entered in the command line (<kbd>:</kbd>)
+//!
+//! Placeholders in the command are replaced by the quoted paths:
+//!
+//! - `%f` - the selected file;
+//! - `%s` - the marked files (or the selected one if nothing is marked);
+//! - `%d` - the current directory;
+//! - `%D` - the directory of the other panel;
+//! - `%%` - the `%` character.
+
+use crate::tui::term;
+
+use anyhow::{anyhow, Result};
+use std::{
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Stdio},
+};
+
+/// Values of the placeholders
+pub struct Placeholders<'a> {
+    pub selected: Option<&'a Path>,
+    pub marked: &'a [PathBuf],
+    pub dir: &'a Path,
+    pub other_dir: &'a Path,
+}
+
+/// Replace the placeholders in the command
+pub fn expand(cmd: &str, values: &Placeholders) -> String {
+    let path = |pth: &Path| quote(&pth.to_string_lossy());
+
+    let mut expanded = String::new();
+    let mut chars = cmd.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            expanded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('f') => expanded.push_str(&values.selected.map(path).unwrap_or_default()),
+            Some('s') => expanded.push_str(
+                &values
+                    .marked
+                    .iter()
+                    .map(|pth| path(pth))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            Some('d') => expanded.push_str(&path(values.dir)),
+            Some('D') => expanded.push_str(&path(values.other_dir)),
+            Some('%') => expanded.push('%'),
+            // Неизвестные сочетания остаются как есть (например, `date +%Y`)
+            Some(other) => {
+                expanded.push('%');
+                expanded.push(other);
+            }
+            None => expanded.push('%'),
+        }
+    }
+    expanded
+}
+
+/// Quote the string for the shell
+pub fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Run the command in the `dir` and get its output (stdout and stderr
+/// together)
+pub fn run_captured(cmd: &str, dir: &Path) -> Result<(String, ExitStatus)> {
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(format!("exec 2>&1\n{cmd}"))
+        .current_dir(dir)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|why| anyhow!("Failed to run '{cmd}': {why}"))?;
+    Ok((
+        String::from_utf8_lossy(&output.stdout).to_string(),
+        output.status,
+    ))
+}
+
+/// Run the command in the `dir` with the terminal. Output of the command is
+/// shown in the terminal even if stdout is redirected
+pub fn run(cmd: &str, dir: &Path) -> Result<ExitStatus> {
+    let mut command = Command::new("sh");
+    // stdout может быть каналом, в который выводятся выбранные файлы
+    if let Some(tty) = term::redirected_tty() {
+        command.stdout(tty);
+    }
+    command
+        .arg("-c")
+        .arg(cmd)
+        .current_dir(dir)
+        .status()
+        .map_err(|why| anyhow!("Failed to run '{cmd}': {why}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand_test() {
+        let marked = [PathBuf::from("/a/x y"), PathBuf::from("/a/it's")];
+        let values = Placeholders {
+            selected: Some(Path::new("/a/x y")),
+            marked: &marked,
+            dir: Path::new("/a"),
+            other_dir: Path::new("/b"),
+        };
+        assert_eq!(
+            expand("cp %s %D && ls %f %d 100%% +%Y %", &values),
+            r"cp '/a/x y' '/a/it'\''s' '/b' && ls '/a/x y' '/a' 100% +%Y %"
+        );
+
+        let (out, status) = run_captured("pwd; echo err >&2; exit 3", Path::new("/")).unwrap();
+        assert_eq!(out, "/\nerr\n");
+        assert_eq!(status.code(), Some(3));
+    }
+}
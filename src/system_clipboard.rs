@@ -0,0 +1,108 @@
+//! Copy text to the system clipboard
+//!
+//! Text is sent to the terminal by the OSC 52 escape sequence, so it works
+//! over SSH and inside `tmux`. If `wl-copy` (Wayland) or `xclip` (X11) are
+//! available, they are used too because some terminals don't support OSC 52.
+
+use crate::tui::term::tty;
+
+use anyhow::{anyhow, Result};
+use std::{
+    env,
+    io::Write,
+    process::{Command, Stdio},
+};
+
+const BASE64_CHARS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+fn base64(data: &[u8]) -> String {
+    let mut s = String::with_capacity(data.len().div_ceil(3) * 4);
+
+    for chunk in data.chunks(3) {
+        let b = [
+            chunk[0],
+            *chunk.get(1).unwrap_or(&0),
+            *chunk.get(2).unwrap_or(&0),
+        ];
+        let n = (b[0] as u32) << 16 | (b[1] as u32) << 8 | b[2] as u32;
+
+        for i in 0..4 {
+            if i <= chunk.len() {
+                s.push(BASE64_CHARS[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                s.push('=');
+            }
+        }
+    }
+
+    s
+}
+
+/// Get OSC 52 sequence for setting the clipboard contents
+fn osc52(text: &str) -> String {
+    let seq = format!("\x1b]52;c;{}\x07", base64(text.as_bytes()));
+
+    if env::var_os("TMUX").is_some() {
+        // tmux принимает OSC 52 только при `set-clipboard on`, а при
+        // `allow-passthrough on` передаёт терминалу последовательности,
+        // обёрнутые в DCS (ESC внутри должен быть удвоен). Отправляем оба
+        // варианта, чтобы не зависеть от настроек tmux
+        format!("{seq}\x1bPtmux;{}\x1b\\", seq.replace('\x1b', "\x1b\x1b"))
+    } else {
+        seq
+    }
+}
+
+/// Pass text to the stdin of the clipboard program
+fn run_program(program: &str, args: &[&str], text: &str) -> Result<()> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    if let Some(stdin) = child.stdin.as_mut() {
+        stdin.write_all(text.as_bytes())?;
+    }
+    // Закрываем stdin, иначе программа будет бесконечно ждать данные
+    drop(child.stdin.take());
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(anyhow!("'{program}' exited with {status}"));
+    }
+    Ok(())
+}
+
+/// Copy text to the system clipboard
+pub fn copy(text: &str) -> Result<()> {
+    // stdout может быть каналом, в который выводятся выбранные файлы
+    let mut tty = tty();
+    tty.write_all(osc52(text).as_bytes())?;
+    tty.flush()?;
+
+    // Ошибки запуска программ игнорируются: их может не быть в системе, а
+    // OSC 52 уже отправлен терминалу
+    if env::var_os("WAYLAND_DISPLAY").is_some() {
+        let _ = run_program("wl-copy", &[], text);
+    } else if env::var_os("DISPLAY").is_some() {
+        let _ = run_program("xclip", &["-selection", "clipboard"], text);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_test() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"/home/user"), "L2hvbWUvdXNlcg==");
+    }
+}
@@ -1,79 +1,354 @@
 //! Text user interface for `f`
 
 pub mod colors;
+pub mod dialog;
 pub mod files;
+pub mod output;
+pub mod panel;
+pub mod tab;
+pub mod term;
 
-use crate::consts::{PROG_NAME, PROG_VER};
-use crate::utils::get_home;
-use crate::{traits::Toml, utils::read_dir, FileEntry, FileType};
+use crate::archive::{self, Archive, Format, Progress};
+use crate::attrs::Attrs;
+use crate::clipboard::{Clipboard, ClipboardMode, Paste, Resolution};
+use crate::consts::{OPENERS_FILE, PROG_NAME, PROG_VER, SESSION_FILE};
+use crate::create::{check_path, create_link, parse_mode, Fill, LinkKind, NewFile};
+use crate::find::{parse_size, parse_time, quote};
+use crate::opener::{self, Openers, Rule};
+use crate::rename::{self, Case, Rename};
+use crate::session::Session;
+use crate::shell::{self, Placeholders};
+use crate::system_clipboard;
+use crate::users::{self, group_name, user_name};
+use crate::vfs::{self, sftp, LocalFs, Vfs};
+use f_core::conf::Conf;
+use f_core::utils::{editor_command, expand_path, get_home};
+use f_core::{traits::Toml, FileSize};
 
 use colors::{color_from_u8, get_style, Colors};
+use dialog::{Dialog, DialogKind, DialogResult};
 
-use anyhow::Result;
-use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
+use anyhow::{anyhow, Result};
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    terminal,
+};
 use files::FilesView;
+use output::Output;
+use panel::Panel;
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Styled, Stylize},
-    text::Line,
-    widgets::TableState,
-    DefaultTerminal, Frame,
+    text::{Line, Span},
+    Frame,
 };
+use tab::Tab;
+use term::Terminal;
 
 use std::{
-    ffi::OsString,
-    path::{Path, PathBuf},
-    str::FromStr,
+    collections::HashMap,
+    fs,
+    io::{self, Write},
+    os::unix::fs::{MetadataExt, OpenOptionsExt},
+    path::{Component, Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, Receiver},
+        Arc,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
 };
 
+/// Number of the commands saved in the session
+const MAX_COMMANDS: usize = 100;
+/// Number of the tabs restored from the session. Launch in the directory
+/// which isn't shown yet opens the new tab, so the oldest tabs are closed
+const MAX_TABS: usize = 10;
+
+/// Action which needs the whole terminal (TUI is suspended while it runs)
+enum External {
+    /// Edit names of the renamed files in the text editor. `list` is the
+    /// temporary file with names (one per line)
+    RenameInEditor { files: Vec<PathBuf>, list: PathBuf },
+
+    /// Open the file in the foreground program
+    Open { rule: Rule, file: PathBuf },
+
+    /// Edit files in the text editor
+    Edit { files: Vec<PathBuf> },
+
+    /// Run the shell command in the `dir` and wait for a key press
+    Shell { command: String, dir: PathBuf },
+}
+
+/// What is chosen in the file chooser mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Choose {
+    /// Files are chosen by <kbd>Enter</kbd> or <kbd>Ctrl+S</kbd>
+    Files,
+    /// Directories are chosen by <kbd>Ctrl+S</kbd> (<kbd>Enter</kbd> opens
+    /// them)
+    Dir,
+}
+
+/// Startup options (from the command line and the configuration file)
+#[derive(Debug, Default)]
+pub struct Options {
+    pub show_hidden: bool,
+    /// File with the color scheme (`./colors.toml` by default)
+    pub theme: Option<PathBuf>,
+    pub conf: Conf,
+    /// Name of the file selected in the start directory
+    pub select: Option<String>,
+    /// Directory of the second panel (two-panel mode is enabled)
+    pub second_dir: Option<PathBuf>,
+}
+
+/// Archive created in the background thread
+struct Archiving {
+    dest: PathBuf,
+    in_current: bool,
+
+    progress: Receiver<Progress>,
+    cancel: Arc<AtomicBool>,
+    thread: JoinHandle<Result<()>>,
+}
+
 /// Main `f` TUI
 pub struct F {
     colors: Colors,
+    /// File with the color scheme (it's reread by <kbd>c</kbd>)
+    theme: PathBuf,
+    openers: Openers,
     show_hidden: bool,
     show_preview: bool,
     show_bytes: bool,
+    /// Show sizes of the files in KBytes, MBytes, etc.
+    human_units: bool,
+    two_panels: bool,
     error_text: Option<String>,
 
-    ts: TableState,
-    current_dir: PathBuf,
-    rows: Vec<FileEntry>,
-    selected: Option<FileEntry>,
-    idx: Option<usize>,
+    /// Opened dialogs (the last one is on top and receives keys)
+    dialogs: Vec<Dialog>,
+    /// Texts entered in the dialogs inputs (by the input ID)
+    input_history: HashMap<&'static str, Vec<String>>,
+
+    /// Clipboard is shared between all tabs and panels
+    clipboard: Option<Clipboard>,
+    /// Pasting paused until user resolves the conflict
+    paste: Option<Paste>,
+
+    /// Action which will be run after handling the current key
+    external: Option<External>,
+    /// Output of the command shown over the panels
+    output: Option<Output>,
+
+    /// Archive which is being created now
+    archiving: Option<Archiving>,
+
+    /// File chooser mode
+    choose: Option<Choose>,
+    /// Paths chosen in the file chooser mode
+    chosen: Vec<PathBuf>,
+
+    tabs: Vec<Tab>,
+    /// Index of the active tab in `tabs`
+    tab: usize,
 
     is_exit: bool,
 }
 
 impl F {
-    pub fn new<P: AsRef<Path>>(pth: P) -> Result<Self> {
-        let rows = read_dir(&pth, false)?;
+    /// Create TUI with tabs restored from the previous session. The `pth`
+    /// directory is opened in the tab which already shows it or in the new
+    /// tab
+    pub fn new<P: AsRef<Path>>(pth: P, options: Options) -> Result<Self> {
+        let show_hidden = options.show_hidden;
+        // Пути панелей абсолютные: с ними сравниваются пути назначения
+        // копирования и директории вкладок из сессии
+        let start_dir = absolute_dir(pth.as_ref())?;
+        let second_dir = options
+            .second_dir
+            .as_deref()
+            .map(absolute_dir)
+            .transpose()?;
+
+        let colors = match &options.theme {
+            Some(theme) => Colors::parse(theme)?,
+            None => Colors::parse("./colors.toml").unwrap_or_default(),
+        };
+
+        let session = Session::parse(get_home().join(SESSION_FILE)).unwrap_or_default();
+        let mut tabs = Vec::new();
+        let mut active = 0;
+        for (idx, state) in session.tabs.iter().enumerate() {
+            // Директории из прошлой сессии могли быть удалены
+            if let Ok(tab) = Tab::from_state(state, show_hidden) {
+                if idx <= session.active_tab {
+                    active = tabs.len();
+                }
+                tabs.push(tab);
+            }
+        }
+
+        // Вкладки из прошлой сессии не меняются: директория открывается во
+        // вкладке, которая уже её показывает (с той же второй панелью, если
+        // она задана), или в новой вкладке
+        let tab = match shown_tab(&tabs, active, &start_dir, second_dir.as_deref()) {
+            Some(idx) => {
+                if second_dir.is_some() {
+                    tabs[idx].active = 0;
+                }
+                idx
+            }
+            None => {
+                let mut start_tab = Tab::new(&start_dir, show_hidden)?;
+                if let Some(dir) = &second_dir {
+                    start_tab.panels[1] = Panel::new(dir, show_hidden)?;
+                }
+                if tabs.len() >= MAX_TABS {
+                    tabs.drain(..=tabs.len() - MAX_TABS);
+                }
+                tabs.push(start_tab);
+                tabs.len() - 1
+            }
+        };
+        if let Some(name) = &options.select {
+            let tab = &mut tabs[tab];
+            tab.panels[tab.active].select_name(name);
+        }
 
         Ok(Self {
-            current_dir: pth.as_ref().to_path_buf(),
-            colors: Colors::parse("./colors.toml").unwrap_or_default(),
-            ts: TableState::default(),
-            selected: if rows.len() > 0 {
-                Some(rows[0].clone())
-            } else {
-                None
-            },
-            rows,
-            idx: None,
+            colors,
+            theme: options
+                .theme
+                .unwrap_or_else(|| PathBuf::from("./colors.toml")),
+            openers: Openers::parse(get_home().join(OPENERS_FILE)).unwrap_or_default(),
+            tabs,
+            tab,
             error_text: None,
-            show_hidden: false,
-            show_preview: true,
+            dialogs: Vec::new(),
+            input_history: HashMap::from([("command", session.commands)]),
+            clipboard: None,
+            paste: None,
+            external: None,
+            output: None,
+            archiving: None,
+            choose: None,
+            chosen: Vec::new(),
+            show_hidden,
+            // Предпросмотр занимает место второй панели
+            show_preview: options.conf.preview_files && options.second_dir.is_none(),
             show_bytes: false,
+            human_units: options.conf.use_human_units,
+            two_panels: options.second_dir.is_some(),
 
             is_exit: false,
         })
     }
 
-    fn rescan_dir(&mut self) -> Result<()> {
-        self.rows = read_dir(&self.current_dir, self.show_hidden)?;
-        if self.rows.len() > 0 {
-            self.idx = Some(0);
-            self.ts.select(self.idx);
+    fn tab(&self) -> &Tab {
+        &self.tabs[self.tab]
+    }
+
+    fn tab_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.tab]
+    }
+
+    fn panel(&self) -> &Panel {
+        let tab = self.tab();
+        &tab.panels[tab.active]
+    }
+
+    fn panel_mut(&mut self) -> &mut Panel {
+        let tab = self.tab_mut();
+        &mut tab.panels[tab.active]
+    }
+
+    /// The inactive panel
+    fn other_panel(&self) -> &Panel {
+        let tab = self.tab();
+        &tab.panels[1 - tab.active]
+    }
+
+    /// Rescan all panels after the file operations, because their results
+    /// may be displayed in the inactive panels and other tabs too. Cursors
+    /// stay on the same files. Error in one panel doesn't stop rescanning of
+    /// the others
+    fn rescan_all(&mut self) -> Result<()> {
+        let errors = self
+            .tabs
+            .iter_mut()
+            .flat_map(|tab| tab.panels.iter_mut())
+            .filter_map(|panel| panel.rescan_dir(self.show_hidden).err())
+            .collect::<Vec<_>>();
+
+        match errors.len() {
+            0 => Ok(()),
+            1 => Err(errors.into_iter().next().unwrap()),
+            n => Err(anyhow!("{} (and {} more errors)", errors[0], n - 1)),
+        }
+    }
+
+    /// Check that the operation is supported by the file system of the
+    /// current panel (see `vfs::check_local`) and show the error otherwise
+    fn check_local(&mut self, operation: &str) -> bool {
+        match vfs::check_local(self.panel().vfs.as_ref(), operation) {
+            Ok(_) => true,
+            Err(why) => {
+                self.error_text = Some(why.to_string());
+                false
+            }
+        }
+    }
+
+    fn new_tab(&mut self) {
+        match Tab::new(&self.panel().current_dir, self.show_hidden) {
+            Ok(tab) => {
+                self.tab += 1;
+                self.tabs.insert(self.tab, tab);
+            }
+            Err(why) => self.error_text = Some(why.to_string()),
+        }
+    }
+
+    fn close_tab(&mut self) {
+        if self.tabs.len() == 1 {
+            self.error_text = Some("Failed to close the last tab".to_string());
+            return;
+        }
+        self.tabs.remove(self.tab);
+        self.tab = self.tab.min(self.tabs.len() - 1);
+    }
+
+    /// Move the active tab to the left (`offset = -1`) or right (`offset = 1`)
+    fn move_tab(&mut self, offset: isize) {
+        let new_idx = self.tab.saturating_add_signed(offset);
+        if new_idx < self.tabs.len() {
+            self.tabs.swap(self.tab, new_idx);
+            self.tab = new_idx;
+        }
+    }
+
+    fn save_session(&self) -> Result<()> {
+        Session {
+            active_tab: self.tab,
+            commands: self
+                .input_history
+                .get("command")
+                .map(|commands| commands[commands.len().saturating_sub(MAX_COMMANDS)..].to_vec())
+                .unwrap_or_default(),
+            tabs: self.tabs.iter().map(|tab| tab.state()).collect(),
+        }
+        .write(get_home().join(SESSION_FILE))
+    }
+
+    fn cd<P: AsRef<Path>>(&mut self, pth: P) {
+        let show_hidden = self.show_hidden;
+        if let Err(why) = self.panel_mut().cd(pth, show_hidden) {
+            self.error_text = Some(why.to_string());
         }
-        Ok(())
     }
 
     fn exit(&mut self) {
@@ -94,109 +369,1374 @@ impl F {
         Ok(())
     }
 
-    fn update_idx(&mut self) {
-        self.rows
-            .get(self.ts.selected().unwrap_or(0))
-            .cloned()
-            .clone_into(&mut self.selected);
-        self.idx = Some(self.ts.selected().unwrap_or(0));
+    /// Open the dialog on top of the others
+    fn open_dialog(&mut self, dialog: Dialog) {
+        self.dialogs.push(dialog.with_history(&self.input_history));
     }
 
-    fn handle_key_event(&mut self, key_event: KeyEvent) {
-        match key_event.code {
-            KeyCode::F(8) => {
-                if let Some(selected) = &self.selected {
-                    if selected.file_name == OsString::from_str(".. [UP]").unwrap()
-                        || self.idx == Some(0)
-                    {
-                        self.error_text = Some("Failed to remove parent directory".to_string());
-                    } else {
-                        if let Err(why) = selected.remove() {
-                            self.error_text = Some(why.to_string());
-                        } else {
-                            if let Err(why) = self.rescan_dir() {
-                                self.error_text = Some(why.to_string());
-                            }
-                        }
-                    }
+    fn handle_dialog_key(&mut self, key_event: KeyEvent) {
+        let Some(dialog) = self.dialogs.last_mut() else {
+            return;
+        };
+
+        match dialog.handle_key(key_event) {
+            DialogResult::None if dialog.kind == DialogKind::Attributes => sync_mode(dialog),
+            DialogResult::None if dialog.kind == DialogKind::Rename => self.update_rename_preview(),
+            DialogResult::None => {}
+            DialogResult::Cancel => {
+                if let Some(dialog) = self.dialogs.pop() {
+                    self.cancel_dialog(&dialog);
                 }
             }
-            KeyCode::Delete => {
-                if let Some(selected) = &self.selected {
-                    if selected.file_name == OsString::from_str(".. [UP]").unwrap()
-                        || self.idx == Some(0)
-                    {
-                        self.error_text = Some("Failed to remove parent directory".to_string());
-                    } else {
-                        if let Err(why) = selected.remove_bin() {
-                            self.error_text = Some(why.to_string());
-                        } else {
-                            if let Err(why) = self.rescan_dir() {
-                                self.error_text = Some(why.to_string());
-                            }
-                        }
+            DialogResult::Button(btn) => {
+                let Some(mut dialog) = self.dialogs.pop() else {
+                    return;
+                };
+                dialog.save_history(&mut self.input_history);
+
+                // При ошибке диалог остаётся открытым, чтобы пользователь
+                // мог исправить введённые данные
+                if let Err(why) = self.submit_dialog(&dialog, btn) {
+                    dialog.error = Some(why.to_string());
+                    self.dialogs.push(dialog);
+                }
+            }
+        }
+    }
+
+    fn cancel_dialog(&mut self, dialog: &Dialog) {
+        match dialog.kind {
+            DialogKind::Conflict => {
+                if let Some(paste) = self.paste.take() {
+                    self.continue_paste(paste, None);
+                }
+            }
+            DialogKind::ArchiveProgress => {
+                if let Some(archiving) = &self.archiving {
+                    archiving.cancel.store(true, Ordering::Relaxed);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn submit_dialog(&mut self, dialog: &Dialog, btn: usize) -> Result<()> {
+        let show_hidden = self.show_hidden;
+
+        match dialog.kind {
+            DialogKind::Find => self.panel_mut().search(find_query(dialog), show_hidden)?,
+            DialogKind::Mark => self
+                .panel_mut()
+                .mark_by_pattern(dialog.value("pattern"), true),
+            DialogKind::Unmark => self
+                .panel_mut()
+                .mark_by_pattern(dialog.value("pattern"), false),
+            DialogKind::Filter => {
+                let panel = self.panel_mut();
+                panel.filter =
+                    Some(dialog.value("pattern").to_string()).filter(|text| !text.is_empty());
+                panel.rescan_dir(show_hidden)?;
+            }
+            DialogKind::Copy => self.copy_targets(dialog.value("dest"), false),
+            DialogKind::Move => self.copy_targets(dialog.value("dest"), true),
+            DialogKind::Conflict => {
+                let res = [Resolution::Overwrite, Resolution::Skip, Resolution::Rename][btn];
+                if let Some(mut paste) = self.paste.take() {
+                    let conflict = paste.resolve(res, dialog.checked("all"));
+                    self.continue_paste(paste, conflict);
+                }
+            }
+            DialogKind::SystemClipboard => self.copy_to_system(dialog.selected("what"))?,
+            DialogKind::GoTo => self.go_to(dialog.value("path"))?,
+            DialogKind::CreateFile => self.create_file(dialog)?,
+            DialogKind::CreateDir => {
+                let warnings = check_path(dialog.value("path"))?;
+                if warnings.is_empty() {
+                    self.create_dir(dialog)?;
+                } else {
+                    // Диалог создания остаётся под подтверждением, чтобы при
+                    // отказе можно было исправить имя
+                    self.dialogs.push(dialog.clone());
+                    self.open_dialog(
+                        Dialog::new(DialogKind::ConfirmCreateDir, "Warning")
+                            .text(warnings.join("\n"))
+                            .text("Create anyway?")
+                            .buttons(&["Create"]),
+                    );
+                }
+            }
+            DialogKind::CreateLink => self.create_link(dialog)?,
+            DialogKind::Attributes => self.change_attrs(dialog)?,
+            DialogKind::Rename if btn == 0 => self.rename_targets(dialog)?,
+            DialogKind::Rename => self.rename_in_editor()?,
+            DialogKind::Archive => self.create_archive(dialog)?,
+            DialogKind::ArchiveProgress => {}
+            DialogKind::Extract => self.extract(dialog)?,
+            DialogKind::Connect => self.connect(dialog)?,
+            DialogKind::OpenWith => self.open_file(dialog.selected("rule"))?,
+            DialogKind::Command => self.run_command(dialog)?,
+            DialogKind::Errors => {}
+            DialogKind::ConfirmCreateDir => {
+                if let Some(mut dialog) = self.dialogs.pop() {
+                    if let Err(why) = self.create_dir(&dialog) {
+                        dialog.error = Some(why.to_string());
+                        self.dialogs.push(dialog);
                     }
                 }
             }
-            KeyCode::F(10) | KeyCode::Char('q') | KeyCode::Char('й') => {
-                self.exit();
+        }
+        Ok(())
+    }
+
+    fn ask_find(&mut self) {
+        if !self.check_local("Search") {
+            return;
+        }
+        let text = self
+            .panel()
+            .search
+            .as_ref()
+            .map(|search| search.text.clone())
+            .unwrap_or_default();
+
+        self.open_dialog(
+            Dialog::new(DialogKind::Find, "Find")
+                .input("query", "Name or query", text)
+                .input("larger", "Larger than", "")
+                .input("smaller", "Smaller than", "")
+                .input("newer", "Modified after", "")
+                .input("older", "Modified before", "")
+                .input("owner", "Owner", "")
+                .input("group", "Group", "")
+                .input("perm", "Permissions", "")
+                .input("depth", "Max depth", "")
+                .radio(
+                    "type",
+                    "Type",
+                    &["Any", "File", "Executable", "Directory", "Link", "Special"],
+                    0,
+                ),
+        );
+    }
+
+    /// Ask for the address of the remote host (or pick it from the `ssh`
+    /// config)
+    fn ask_connect(&mut self) {
+        let hosts = sftp::known_hosts();
+        let mut dialog = Dialog::new(DialogKind::Connect, "Connect (SFTP)")
+            .input("url", "Address", sftp::SCHEME)
+            .buttons(&["Connect"]);
+        if !hosts.is_empty() {
+            let options = ["Entered address".to_string()]
+                .into_iter()
+                .chain(hosts)
+                .collect::<Vec<_>>();
+            dialog = dialog.select("host", "Host from ~/.ssh/config", &options, 0);
+        }
+        self.open_dialog(dialog);
+    }
+
+    fn connect(&mut self, dialog: &Dialog) -> Result<()> {
+        let url = match dialog.selected("host") {
+            0 => dialog.value("url").trim().to_string(),
+            idx => format!("{}{}", sftp::SCHEME, sftp::known_hosts()[idx - 1]),
+        };
+        if !vfs::is_url(Path::new(&url)) {
+            return Err(anyhow!(
+                "Wrong address '{url}' (expected sftp://[user@]host[:port][/path])"
+            ));
+        }
+        let show_hidden = self.show_hidden;
+        self.panel_mut().cd(url, show_hidden)
+    }
+
+    fn ask_go_to(&mut self) {
+        let base = self.panel().current_dir.clone();
+        self.open_dialog(
+            Dialog::new(DialogKind::GoTo, "Go to").path_input("path", "Path", "", base),
+        );
+    }
+
+    /// Go to the directory or to the parent directory of the file and select
+    /// this file
+    fn go_to(&mut self, pth: &str) -> Result<()> {
+        let panel = self.panel();
+        let pth = vfs::join(&panel.current_dir, &expand_path(pth));
+        let show_hidden = self.show_hidden;
+        // Другая ФС (архив или удалённый хост) открывается при переходе
+        if !panel.vfs.contains(&pth) {
+            return self.panel_mut().cd(pth, show_hidden);
+        }
+        let pth = panel.vfs.canonicalize(&pth)?;
+
+        if panel.vfs.stat(&pth).is_ok_and(|stat| stat.is_dir()) {
+            return self.panel_mut().cd(pth, show_hidden);
+        }
+        let (Some(parent), Some(name)) = (pth.parent(), pth.file_name()) else {
+            return Err(anyhow!("Failed to open '{}'", pth.display()));
+        };
+        let panel = self.panel_mut();
+        panel.cd(parent, show_hidden)?;
+        panel.select_name(&name.to_string_lossy());
+        Ok(())
+    }
+
+    fn ask_create_file(&mut self) {
+        if !self.check_local("Creating files") {
+            return;
+        }
+        self.open_dialog(
+            Dialog::new(DialogKind::CreateFile, "New file")
+                .input("name", "Name", "")
+                .input("size", "Size", "0")
+                .input("mode", "Permissions", "644")
+                .input("atime", "Access time", "")
+                .input("mtime", "Modification time", "")
+                .radio("fill", "Fill", &["Sparse", "Zeros", "Random", "Pattern"], 0)
+                .input("pattern", "Pattern", ""),
+        );
+    }
+
+    fn create_file(&mut self, dialog: &Dialog) -> Result<()> {
+        vfs::check_local(self.panel().vfs.as_ref(), "Creating files")?;
+        let name = dialog.value("name");
+        check_path(name)?;
+        // Пустое поле времени означает текущее время
+        let time = |id| match dialog.value(id).trim() {
+            "" => Ok(None),
+            s => parse_time(s).map(Some),
+        };
+
+        let new_file = NewFile {
+            size: match dialog.value("size").trim() {
+                "" => 0,
+                s => parse_size(s)?,
+            },
+            fill: match dialog.selected("fill") {
+                0 => Fill::Sparse,
+                1 => Fill::Zeros,
+                2 => Fill::Random,
+                _ => Fill::Pattern(dialog.value("pattern").as_bytes().to_vec()),
+            },
+            atime: time("atime")?,
+            mtime: time("mtime")?,
+            mode: parse_mode(dialog.value("mode"))?,
+        };
+
+        new_file.create(self.panel().current_dir.join(name))?;
+        self.select_created(name)
+    }
+
+    fn ask_create_dir(&mut self) {
+        self.open_dialog(
+            Dialog::new(DialogKind::CreateDir, "New directory")
+                .input("path", "Path", "")
+                .input("mode", "Permissions", "755"),
+        );
+    }
+
+    fn create_dir(&mut self, dialog: &Dialog) -> Result<()> {
+        let pth = dialog.value("path");
+        let panel = self.panel();
+        panel.vfs.mkdir(
+            &panel.current_dir.join(pth),
+            parse_mode(dialog.value("mode"))?,
+        )?;
+        self.select_created(pth)
+    }
+
+    fn ask_create_link(&mut self) {
+        if !self.check_local("Creating links") {
+            return;
+        }
+        let Some(selected) = self
+            .panel()
+            .selected
+            .clone()
+            .filter(|entry| !entry.is_parent())
+        else {
+            self.error_text = Some("Nothing to link".to_string());
+            return;
+        };
+        let name = selected
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+
+        self.open_dialog(
+            Dialog::new(DialogKind::CreateLink, "New link")
+                .text(format!("Link to '{}'", selected.path.display()))
+                .input("name", "Name", name)
+                .radio(
+                    "place",
+                    "Create in",
+                    &["Current directory", "Directory of the other panel"],
+                    usize::from(self.two_panels),
+                )
+                .radio(
+                    "kind",
+                    "Type",
+                    &[
+                        "Symbolic (absolute path)",
+                        "Symbolic (relative path)",
+                        "Hard",
+                    ],
+                    0,
+                ),
+        );
+    }
+
+    fn create_link(&mut self, dialog: &Dialog) -> Result<()> {
+        let Some(selected) = self.panel().selected.clone() else {
+            return Ok(());
+        };
+        let name = dialog.value("name");
+        check_path(name)?;
+
+        let in_current = dialog.selected("place") == 0;
+        let panel = if in_current {
+            self.panel()
+        } else {
+            self.other_panel()
+        };
+        vfs::check_local(panel.vfs.as_ref(), "Creating links")?;
+        let dir = &panel.current_dir;
+        let kind = match dialog.selected("kind") {
+            0 => LinkKind::Absolute,
+            1 => LinkKind::Relative,
+            _ => LinkKind::Hard,
+        };
+        create_link(&selected.path, &dir.join(name), kind)?;
+
+        if in_current {
+            self.select_created(name)
+        } else {
+            self.rescan_all()
+        }
+    }
+
+    /// Show all errors of the operation (one line per file)
+    fn show_errors(&mut self, title: &str, errors: Vec<anyhow::Error>) {
+        const MAX_ERRORS: usize = 15;
+        if errors.is_empty() {
+            return;
+        }
+
+        let mut text = errors
+            .iter()
+            .take(MAX_ERRORS)
+            .map(|why| why.to_string())
+            .collect::<Vec<_>>();
+        if errors.len() > MAX_ERRORS {
+            text.push(format!("... and {} more", errors.len() - MAX_ERRORS));
+        }
+        self.open_dialog(
+            Dialog::new(DialogKind::Errors, title)
+                .text(text.join("\n"))
+                .buttons(&[])
+                .cancel_label("Close"),
+        );
+    }
+
+    fn ask_attrs(&mut self) {
+        if !self.check_local("Changing permissions") {
+            return;
+        }
+        let targets = self.panel().targets();
+        let Some(first) = targets.first() else {
+            self.error_text = Some("Nothing to change".to_string());
+            return;
+        };
+        let meta = match first.path.symlink_metadata() {
+            Ok(meta) => meta,
+            Err(why) => {
+                self.error_text = Some(why.to_string());
+                return;
             }
+        };
+
+        let owner = user_name(meta.uid()).unwrap_or(meta.uid().to_string());
+        let group = group_name(meta.gid()).unwrap_or(meta.gid().to_string());
+        let text = match targets.len() {
+            1 => format!("'{}'", first.path.display()),
+            n => format!(
+                "{n} files (values of '{}')",
+                first.file_name.to_string_lossy()
+            ),
+        };
+        // Первый вариант оставляет владельца (группу) без изменений
+        let accounts = |accounts: Vec<users::Account>, current: String| {
+            [format!("{current} (unchanged)")]
+                .into_iter()
+                .chain(accounts.into_iter().map(|acc| acc.name))
+                .collect::<Vec<_>>()
+        };
+
+        let mut dialog = Dialog::new(DialogKind::Attributes, "Permissions and owner")
+            .text(text)
+            .grid(
+                "perm",
+                "Permissions",
+                &["Owner", "Group", "Other"],
+                &["r", "w", "x"],
+                &[false; 9],
+            )
+            .checkbox("suid", "Set user ID", false)
+            .checkbox("sgid", "Set group ID", false)
+            .checkbox("sticky", "Sticky", false)
+            .input("octal", "Octal", "")
+            .select("owner", "Owner", &accounts(users::users(), owner), 0)
+            .select("group", "Group", &accounts(users::groups(), group), 0)
+            .checkbox("recursive", "Recursive", false)
+            .input("file_mask", "File mask", "7777")
+            .input("dir_mask", "Directory mask", "7777");
+        set_mode(&mut dialog, meta.mode() & 0o7777);
+        self.open_dialog(dialog);
+    }
+
+    fn change_attrs(&mut self, dialog: &Dialog) -> Result<()> {
+        vfs::check_local(self.panel().vfs.as_ref(), "Changing permissions")?;
+        let targets = self.panel().targets();
+        let Some(first) = targets.first() else {
+            return Ok(());
+        };
+        let mode = parse_mode(dialog.value("octal"))?;
+        let old_mode = first.path.symlink_metadata()?.mode() & 0o7777;
+        let recursive = dialog.checked("recursive");
+
+        let attrs = Attrs {
+            // Права нескольких файлов могут отличаться от прав первого, поэтому
+            // без изменений в диалоге они не трогаются
+            mode: Some(mode).filter(|mode| *mode != old_mode || recursive),
+            file_mask: parse_mode(dialog.value("file_mask"))?,
+            dir_mask: parse_mode(dialog.value("dir_mask"))?,
+            uid: match dialog.selected("owner") {
+                0 => None,
+                idx => users::users().get(idx - 1).map(|acc| acc.id),
+            },
+            gid: match dialog.selected("group") {
+                0 => None,
+                idx => users::groups().get(idx - 1).map(|acc| acc.id),
+            },
+            recursive,
+        };
 
-            KeyCode::Down | KeyCode::Char('j') => {
-                if self.idx.unwrap_or(0) < (self.rows.len() - 1) {
-                    self.ts.select_next();
-                    self.update_idx();
+        let mut errors = Vec::new();
+        for entry in &targets {
+            attrs.apply(&entry.path, &mut errors);
+        }
+        self.panel_mut().marked.clear();
+        self.rescan_all()?;
+        self.show_errors("Failed to change attributes", errors);
+        Ok(())
+    }
+
+    fn ask_rename(&mut self) {
+        if self.panel().targets().is_empty() {
+            self.error_text = Some("Nothing to rename".to_string());
+            return;
+        }
+
+        self.open_dialog(
+            Dialog::new(DialogKind::Rename, "Rename files")
+                .input("find", "Find (regex)", "")
+                .input("replace", "Replace with", "")
+                .text("$1, ${name} - captured groups; {n}, {n:03} - counter")
+                .input("ext", "Extension", "")
+                .text("Empty extension keeps the old one, '.' removes it")
+                .input("start", "Counter start", "1")
+                .radio("case", "Case", &["Keep", "lower", "UPPER", "Title"], 0)
+                .text_with_id("preview", "")
+                .buttons(&["Rename", "Edit in $EDITOR"]),
+        );
+        self.update_rename_preview();
+    }
+
+    /// Names of the renamed files and their new names made by the rules of
+    /// the rename dialog
+    fn rename_names(&self, dialog: &Dialog) -> Result<(Vec<PathBuf>, Vec<String>)> {
+        let case = match dialog.selected("case") {
+            0 => Case::Keep,
+            1 => Case::Lower,
+            2 => Case::Upper,
+            _ => Case::Title,
+        };
+        let start = match dialog.value("start").trim() {
+            "" => 1,
+            start => start
+                .parse()
+                .map_err(|_| anyhow!("Wrong counter start: '{start}'"))?,
+        };
+        let rename = Rename::new(
+            dialog.value("find"),
+            dialog.value("replace"),
+            case,
+            dialog.value("ext"),
+            start,
+        )?;
+
+        let files = self
+            .panel()
+            .targets()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        let names = files
+            .iter()
+            .enumerate()
+            .map(|(idx, pth)| {
+                let name = pth.file_name().unwrap_or_default().to_string_lossy();
+                rename.apply(&name, idx)
+            })
+            .collect();
+        Ok((files, names))
+    }
+
+    /// Show the old and new names in the rename dialog
+    fn update_rename_preview(&mut self) {
+        const MAX_PREVIEW: usize = 8;
+        let Some(dialog) = self.dialogs.last() else {
+            return;
+        };
+
+        let (preview, error) = match self.rename_names(dialog) {
+            Ok((files, names)) => {
+                let old_names = files
+                    .iter()
+                    .map(|pth| pth.file_name().unwrap_or_default().to_string_lossy())
+                    .collect::<Vec<_>>();
+                let width = old_names.iter().map(|name| name.chars().count()).max();
+
+                let mut preview = old_names
+                    .iter()
+                    .zip(&names)
+                    .take(MAX_PREVIEW)
+                    .map(|(old, new)| format!("{old:<w$} → {new}", w = width.unwrap_or(0)))
+                    .collect::<Vec<_>>();
+                if files.len() > MAX_PREVIEW {
+                    preview.push(format!("... and {} more", files.len() - MAX_PREVIEW));
                 }
+                let error = rename::plan(self.panel().vfs.as_ref(), &files, &names).err();
+                (preview.join("\n"), error)
             }
-            KeyCode::Up | KeyCode::Char('k') => {
-                self.ts.select_previous();
-                self.update_idx();
+            Err(why) => (String::new(), Some(why)),
+        };
+
+        if let Some(dialog) = self.dialogs.last_mut() {
+            dialog.set_text("preview", format!("\nPreview:\n{preview}"));
+            dialog.error = error.map(|why| why.to_string());
+        }
+    }
+
+    fn rename_targets(&mut self, dialog: &Dialog) -> Result<()> {
+        let (files, names) = self.rename_names(dialog)?;
+        self.rename_files(&files, &names)
+    }
+
+    fn rename_files(&mut self, files: &[PathBuf], names: &[String]) -> Result<()> {
+        let fs = self.panel().vfs.as_ref();
+        let renames = rename::plan(fs, files, names)?;
+        let errors = rename::rename_all(fs, &renames);
+
+        self.panel_mut().marked.clear();
+        self.rescan_all()?;
+        self.show_errors("Failed to rename files", errors);
+        Ok(())
+    }
+
+    /// Write names of the renamed files to the temporary file, which will be
+    /// edited by user in the text editor
+    fn rename_in_editor(&mut self) -> Result<()> {
+        let files = self
+            .panel()
+            .targets()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        let names = files
+            .iter()
+            .map(|pth| pth.file_name().unwrap_or_default().to_string_lossy())
+            .collect::<Vec<_>>();
+        if names.iter().any(|name| name.contains('\n')) {
+            return Err(anyhow!(
+                "Names with line breaks can't be edited in the editor"
+            ));
+        }
+
+        // В общем `/tmp` на месте файла может оказаться чужая ссылка, поэтому
+        // файл всегда создаётся заново и доступен только пользователю
+        let mut idx = 0;
+        let (list, mut file) = loop {
+            let list =
+                std::env::temp_dir().join(format!("f-rename-{}-{idx}.txt", std::process::id()));
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .mode(0o600)
+                .open(&list)
+            {
+                Ok(file) => break (list, file),
+                Err(why) if why.kind() == io::ErrorKind::AlreadyExists && idx < 100 => idx += 1,
+                Err(why) => return Err(anyhow!("Failed to create '{}': {}", list.display(), why)),
             }
-            KeyCode::Home | KeyCode::Char('H') => {
-                if self.rows.len() > 0 {
-                    self.idx = Some(0);
-                    self.ts.select(self.idx);
+        };
+        if let Err(why) = file.write_all((names.join("\n") + "\n").as_bytes()) {
+            let _ = fs::remove_file(&list);
+            return Err(anyhow!("Failed to write '{}': {}", list.display(), why));
+        }
+        self.external = Some(External::RenameInEditor { files, list });
+        Ok(())
+    }
+
+    fn run_external(&mut self, external: External) -> Result<()> {
+        match external {
+            External::RenameInEditor { files, list } => {
+                let status = editor_command(&list).status();
+                let names = fs::read_to_string(&list);
+                let _ = fs::remove_file(&list);
+
+                match status {
+                    Ok(status) if !status.success() => {
+                        return Err(anyhow!("Editor exited with {status}, files aren't renamed"));
+                    }
+                    Err(why) => return Err(anyhow!("Failed to run the editor: {why}")),
+                    Ok(_) => {}
                 }
+                let names = names?
+                    .trim_end_matches('\n')
+                    .lines()
+                    .map(|name| name.to_string())
+                    .collect::<Vec<_>>();
+                self.rename_files(&files, &names)
             }
-            KeyCode::End | KeyCode::Char('L') => {
-                if self.rows.len() > 0 {
-                    self.idx = Some(self.rows.len() - 1);
-                    self.ts.select(self.idx);
+            External::Edit { files } => {
+                let mut cmd = editor_command(&files[0]);
+                let status = cmd
+                    .args(&files[1..])
+                    .status()
+                    .map_err(|why| anyhow!("Failed to run the editor: {why}"))?;
+                // Размеры изменённых файлов должны обновиться
+                self.rescan_all()?;
+                match status.success() {
+                    true => Ok(()),
+                    false => Err(anyhow!("Editor exited with {status}")),
                 }
             }
-            KeyCode::Char('~') => {
-                let pth = get_home();
-                self.selected = Some(FileEntry {
-                    file_name: OsString::from_str("~").unwrap(),
-                    path: pth.clone(),
-                    byte_size: 4096,
-                    file_type: FileType::Directory,
-                    is_hidden: false,
-                });
-                self.current_dir = pth;
-
-                if let Err(why) = self.rescan_dir() {
-                    self.error_text = Some(why.to_string());
+            External::Open { rule, file } => {
+                rule.run(&file)?;
+                // Программа могла изменить файлы
+                self.rescan_all()
+            }
+            External::Shell { command, dir } => {
+                let status = shell::run(&command, &dir)?;
+                // stdout может быть каналом, в который выводятся выбранные файлы
+                let mut tty = term::tty();
+                write!(tty, "\n[{status}] Press any key to return to {PROG_NAME}")?;
+                tty.flush()?;
+                terminal::enable_raw_mode()?;
+                while !matches!(event::read()?, Event::Key(key) if key.kind == KeyEventKind::Press)
+                {
                 }
+                terminal::disable_raw_mode()?;
+                self.rescan_all()
             }
-            KeyCode::Char('/') => {
-                let pth = Path::new("/").to_path_buf();
-                self.selected = Some(FileEntry {
-                    file_name: OsString::from_str("~").unwrap(),
-                    path: pth.clone(),
-                    byte_size: 4096,
-                    file_type: FileType::Directory,
-                    is_hidden: false,
-                });
-                self.current_dir = pth;
-
-                if let Err(why) = self.rescan_dir() {
-                    self.error_text = Some(why.to_string());
+        }
+    }
+
+    fn ask_command(&mut self) {
+        self.open_dialog(
+            Dialog::new(DialogKind::Command, "Command")
+                .input("command", "Command", "")
+                .text("%f - selected file, %s - marked files, %d - current directory, %D - directory of the other panel")
+                .radio(
+                    "output",
+                    "Output",
+                    &["Show in the window", "Run in the terminal"],
+                    0,
+                )
+                .buttons(&["Run"]),
+        );
+    }
+
+    /// Run the command from the command line in the current directory
+    fn run_command(&mut self, dialog: &Dialog) -> Result<()> {
+        let panel = self.panel();
+        if !panel.vfs.is_local() {
+            return Err(anyhow!("Commands can be run only in the local directories"));
+        }
+        let command = dialog.value("command").trim();
+        if command.is_empty() {
+            return Err(anyhow!("Enter the command"));
+        }
+
+        let marked = panel
+            .targets()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        let values = Placeholders {
+            selected: panel
+                .selected
+                .as_ref()
+                .filter(|entry| !entry.is_parent())
+                .map(|entry| entry.path.as_path()),
+            marked: &marked,
+            dir: &panel.current_dir,
+            other_dir: &self.other_panel().current_dir,
+        };
+        let command = shell::expand(command, &values);
+        let dir = panel.current_dir.clone();
+
+        if dialog.selected("output") == 1 {
+            self.external = Some(External::Shell { command, dir });
+            return Ok(());
+        }
+        let (text, status) = shell::run_captured(&command, &dir)?;
+        let mut output = Output::new(command, &text);
+        if !status.success() {
+            output.lines.push(format!("[{status}]"));
+        }
+        self.output = Some(output);
+        self.rescan_all()
+    }
+
+    /// Edit the marked (or selected) files in `$VISUAL`/`$EDITOR`
+    fn edit_targets(&mut self) {
+        let panel = self.panel();
+        if !panel.vfs.is_local() {
+            self.error_text = Some("Only local files can be edited".to_string());
+            return;
+        }
+        let files = panel
+            .targets()
+            .into_iter()
+            .filter(|entry| !entry.path.is_dir())
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        if files.is_empty() {
+            self.error_text = Some("Nothing to edit".to_string());
+            return;
+        }
+        self.external = Some(External::Edit { files });
+    }
+
+    /// Programs which may open the selected file
+    fn openers(&self) -> Result<(PathBuf, Vec<&Rule>)> {
+        let panel = self.panel();
+        let Some(selected) = panel.selected.as_ref().filter(|entry| !entry.is_parent()) else {
+            return Err(anyhow!("Nothing to open"));
+        };
+        if !panel.vfs.is_local() {
+            return Err(anyhow!(
+                "Only local files can be opened (copy '{}' first)",
+                selected.file_name.to_string_lossy()
+            ));
+        }
+
+        let rules = self.openers.matching(&selected.path);
+        if rules.is_empty() {
+            return Err(anyhow!(
+                "No program for '{}' ({})",
+                selected.file_name.to_string_lossy(),
+                opener::mime_type(&selected.path)
+            ));
+        }
+        Ok((selected.path.clone(), rules))
+    }
+
+    /// Open the selected file by the rule with `idx` from the matching ones
+    fn open_file(&mut self, idx: usize) -> Result<()> {
+        let (file, rules) = self.openers()?;
+        let Some(rule) = rules.get(idx).map(|rule| (*rule).clone()) else {
+            return Ok(());
+        };
+        match rule.background {
+            true => rule.spawn(&file),
+            false => {
+                self.external = Some(External::Open { rule, file });
+                Ok(())
+            }
+        }
+    }
+
+    fn ask_open_with(&mut self) {
+        let names = match self.openers() {
+            Ok((_, rules)) => rules
+                .iter()
+                .map(|rule| match rule.background {
+                    true => format!("{} (background)", rule.name()),
+                    false => rule.name().to_string(),
+                })
+                .collect::<Vec<_>>(),
+            Err(why) => {
+                self.error_text = Some(why.to_string());
+                return;
+            }
+        };
+        let names = names.iter().map(|name| name.as_str()).collect::<Vec<_>>();
+        self.open_dialog(
+            Dialog::new(DialogKind::OpenWith, "Open with")
+                .radio("rule", "Program", &names, 0)
+                .buttons(&["Open"]),
+        );
+    }
+
+    fn ask_archive(&mut self) {
+        if !self.check_local("Creating archives") {
+            return;
+        }
+        let targets = self.panel().targets();
+        let name = match targets.as_slice() {
+            [] => {
+                self.error_text = Some("Nothing to archive".to_string());
+                return;
+            }
+            [entry] => entry.path.file_name(),
+            _ => self.panel().current_dir.file_name(),
+        }
+        .map_or("archive".to_string(), |name| {
+            name.to_string_lossy().to_string()
+        });
+
+        self.open_dialog(
+            Dialog::new(DialogKind::Archive, "Create archive")
+                .text(format!("Archive {} file(s)", targets.len()))
+                .input("name", "Name", name)
+                .radio("format", "Format", &["tar", "tar.gz", "tar.zst", "zip"], 1)
+                .input("level", "Compression level", "")
+                .text("tar.gz, zip: 0-9; tar.zst: 1-19; empty - default")
+                .checkbox("exclude_hidden", "Exclude hidden files", false)
+                .radio(
+                    "place",
+                    "Create in",
+                    &["Current directory", "Directory of the other panel"],
+                    usize::from(self.two_panels),
+                )
+                .buttons(&["Create"]),
+        );
+    }
+
+    /// Start creating the archive in the background thread
+    fn create_archive(&mut self, dialog: &Dialog) -> Result<()> {
+        vfs::check_local(self.panel().vfs.as_ref(), "Creating archives")?;
+        let format = Format::ALL[dialog.selected("format")];
+        let level = match dialog.value("level").trim() {
+            "" => format.default_level(),
+            level => level
+                .parse()
+                .map_err(|_| anyhow!("Wrong compression level: '{level}'"))?,
+        };
+        format.check_level(level)?;
+        let archive = Archive {
+            format,
+            level,
+            exclude_hidden: dialog.checked("exclude_hidden"),
+        };
+
+        let mut name = dialog.value("name").trim().to_string();
+        check_path(&name)?;
+        if !name.ends_with(format.ext()) {
+            name.push_str(format.ext());
+        }
+        let in_current = dialog.selected("place") == 0;
+        let panel = if in_current {
+            self.panel()
+        } else {
+            self.other_panel()
+        };
+        vfs::check_local(panel.vfs.as_ref(), "Creating archives")?;
+        let dest = panel.current_dir.join(&name);
+        if dest.symlink_metadata().is_ok() {
+            return Err(anyhow!("'{}' already exists", dest.display()));
+        }
+
+        let files = self
+            .panel()
+            .targets()
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        let (sender, progress) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        let thread = {
+            let (dest, cancel) = (dest.clone(), cancel.clone());
+            thread::spawn(move || {
+                archive.create(&files, &dest, &mut |progress| {
+                    let _ = sender.send(progress.clone());
+                    !cancel.load(Ordering::Relaxed)
+                })
+            })
+        };
+
+        self.archiving = Some(Archiving {
+            dest,
+            in_current,
+            progress,
+            cancel,
+            thread,
+        });
+        self.open_dialog(
+            Dialog::new(DialogKind::ArchiveProgress, format!("Creating {name}"))
+                .text_with_id("progress", "Preparing...")
+                .buttons(&[])
+                .cancel_label("Abort"),
+        );
+        Ok(())
+    }
+
+    /// Show the progress of the archive creation and finish it when the
+    /// background thread exits
+    fn update_archiving(&mut self) {
+        const BAR_WIDTH: usize = 40;
+        let Some(archiving) = &self.archiving else {
+            return;
+        };
+
+        if let Some(progress) = archiving.progress.try_iter().last() {
+            let part = match progress.total {
+                0 => 1.,
+                total => (progress.done as f64 / total as f64).clamp(0., 1.),
+            };
+            let filled = (part * BAR_WIDTH as f64) as usize;
+            let text = format!(
+                "{}\n{}{} {:.0}%\n{} of {}",
+                progress.file.display(),
+                "█".repeat(filled),
+                "░".repeat(BAR_WIDTH.saturating_sub(filled)),
+                part * 100.,
+                FileSize::get_human_size(progress.done),
+                FileSize::get_human_size(progress.total),
+            );
+            if let Some(dialog) = self
+                .dialogs
+                .iter_mut()
+                .find(|dialog| dialog.kind == DialogKind::ArchiveProgress)
+            {
+                dialog.set_text("progress", text);
+            }
+        }
+
+        if !archiving.thread.is_finished() {
+            return;
+        }
+        let Some(archiving) = self.archiving.take() else {
+            return;
+        };
+        self.dialogs
+            .retain(|dialog| dialog.kind != DialogKind::ArchiveProgress);
+
+        let rslt = archiving
+            .thread
+            .join()
+            .unwrap_or_else(|_| Err(anyhow!("Archiving thread panicked")))
+            .and_then(|_| self.rescan_all());
+        match rslt {
+            Ok(_) if archiving.in_current => {
+                self.panel_mut().marked.clear();
+                let name = archiving.dest.file_name().unwrap_or_default();
+                self.panel_mut().select_name(&name.to_string_lossy());
+            }
+            Ok(_) => self.panel_mut().marked.clear(),
+            Err(why) => self.error_text = Some(why.to_string()),
+        }
+    }
+
+    /// Archive for extracting files: the opened one or the selected archive
+    /// file
+    fn extract_source(&self) -> Option<PathBuf> {
+        let panel = self.panel();
+        match (vfs::archive_file(&panel.current_dir), &panel.selected) {
+            (Some(file), _) => Some(file.to_path_buf()),
+            (None, Some(selected)) if Format::from_path(&selected.path).is_some() => {
+                Some(selected.path.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn ask_extract(&mut self) {
+        let Some(file) = self.extract_source() else {
+            self.error_text = Some("Not an archive".to_string());
+            return;
+        };
+        let panel = self.panel();
+        let members = match vfs::archive_file(&panel.current_dir) {
+            Some(_) => panel.targets().len(),
+            None => 0,
+        };
+
+        let mut dialog = Dialog::new(DialogKind::Extract, "Extract")
+            .text(format!("Extract from '{}'", file.display()));
+        if members > 0 {
+            dialog = dialog.radio(
+                "what",
+                "Extract",
+                &[&format!("{members} selected file(s)"), "Whole archive"],
+                0,
+            );
+        }
+        self.open_dialog(
+            dialog
+                .path_input(
+                    "dest",
+                    "To",
+                    self.other_panel().current_dir.display(),
+                    &panel.current_dir,
+                )
+                .buttons(&["Extract"]),
+        );
+    }
+
+    fn extract(&mut self, dialog: &Dialog) -> Result<()> {
+        let Some(file) = self.extract_source() else {
+            return Ok(());
+        };
+        let panel = self.panel();
+        let names = match vfs::archive_file(&panel.current_dir) {
+            Some(_) if dialog.selected("what") == 0 => panel
+                .targets()
+                .iter()
+                .filter_map(|entry| entry.path.strip_prefix(&file).ok())
+                .map(|name| name.to_path_buf())
+                .collect(),
+            _ => Vec::new(),
+        };
+        let dest = vfs::join(
+            &panel.current_dir,
+            &expand_path(dialog.value("dest").trim()),
+        );
+        // Назначение может оказаться в панели с архивом или на сервере, а
+        // распаковывать можно только в локальные каталоги
+        match [self.other_panel(), panel]
+            .into_iter()
+            .find(|panel| panel.vfs.contains(&dest))
+        {
+            Some(panel) => vfs::check_local(panel.vfs.as_ref(), "Extracting")?,
+            None if !LocalFs.contains(&dest) => {
+                return Err(anyhow!(
+                    "Extracting to '{}' is not supported",
+                    dest.display()
+                ))
+            }
+            None => {}
+        }
+
+        archive::extract(&file, &names, &dest)?;
+        self.panel_mut().marked.clear();
+        self.rescan_all()
+    }
+
+    /// Rescan panels and move cursor to the created file (or to the first
+    /// component of its path)
+    fn select_created(&mut self, pth: &str) -> Result<()> {
+        self.rescan_all()?;
+        if let Some(Component::Normal(name)) = Path::new(pth).components().next() {
+            self.panel_mut().select_name(&name.to_string_lossy());
+        }
+        Ok(())
+    }
+
+    /// Ask for the pattern for marking, unmarking or filtering files
+    fn ask_pattern(&mut self, kind: DialogKind) {
+        let (title, text) = match kind {
+            DialogKind::Mark => ("Mark files", "*".to_string()),
+            DialogKind::Unmark => ("Unmark files", "*".to_string()),
+            _ => ("Filter", self.panel().filter.clone().unwrap_or_default()),
+        };
+        self.open_dialog(Dialog::new(kind, title).input("pattern", "Pattern", text));
+    }
+
+    fn set_errors(&mut self, errors: Vec<anyhow::Error>) {
+        self.error_text = match errors.len() {
+            0 => None,
+            1 => Some(errors[0].to_string()),
+            n => Some(format!("{} (and {} more errors)", errors[0], n - 1)),
+        };
+    }
+
+    fn remove_targets(&mut self, permanently: bool) {
+        let targets = self.panel().targets();
+        if targets.is_empty() {
+            self.error_text = Some("Failed to remove parent directory".to_string());
+            return;
+        }
+        if !permanently && !self.check_local("Recycle bin") {
+            return;
+        }
+
+        let errors = targets
+            .iter()
+            .filter_map(|entry| {
+                if permanently {
+                    self.panel().vfs.remove(&entry.path)
+                } else {
+                    entry.remove_bin()
+                }
+                .err()
+            })
+            .collect::<Vec<_>>();
+        self.panel_mut().marked.clear();
+
+        if let Err(why) = self.rescan_all() {
+            self.error_text = Some(why.to_string());
+        } else {
+            self.set_errors(errors);
+        }
+    }
+
+    /// Ask for the destination of the copied (moved) files. The directory of
+    /// the other panel is used by default
+    fn ask_copy_dest(&mut self, kind: DialogKind) {
+        if self.panel().targets().is_empty() {
+            self.error_text = Some("Nothing to copy or move".to_string());
+            return;
+        }
+
+        let dest = if self.two_panels {
+            self.other_panel().current_dir.clone()
+        } else {
+            self.panel().current_dir.clone()
+        };
+        let dest = fs::canonicalize(&dest).unwrap_or(dest);
+        let title = match kind {
+            DialogKind::Move => "Move",
+            _ => "Copy",
+        };
+        let text = format!("{} {} files", title, self.panel().targets().len());
+        self.open_dialog(Dialog::new(kind, title).text(text).input(
+            "dest",
+            "",
+            dest.to_string_lossy(),
+        ));
+    }
+
+    fn copy_targets(&mut self, dest: &str, is_move: bool) {
+        let panel = self.panel();
+        let dest = vfs::join(&panel.current_dir, Path::new(dest));
+        let errors = if panel.vfs.is_local() && LocalFs.contains(&dest) {
+            panel
+                .targets()
+                .iter()
+                .filter_map(|entry| {
+                    if is_move {
+                        entry.move_to(&dest)
+                    } else {
+                        entry.copy_to(&dest)
+                    }
+                    .err()
+                })
+                .collect::<Vec<_>>()
+        } else {
+            self.copy_between(&dest, is_move)
+        };
+        self.panel_mut().marked.clear();
+
+        if let Err(why) = self.rescan_all() {
+            self.error_text = Some(why.to_string());
+        } else {
+            self.set_errors(errors);
+        }
+    }
+
+    /// Copy (move) files between the file systems, e.g. from the local
+    /// directory to the remote host
+    fn copy_between(&self, dest: &Path, is_move: bool) -> Vec<anyhow::Error> {
+        let (panel, other) = (self.panel(), self.other_panel());
+        let opened;
+        let dest_fs = if panel.vfs.contains(dest) {
+            panel.vfs.as_ref()
+        } else if other.vfs.contains(dest) {
+            other.vfs.as_ref()
+        } else {
+            match vfs::open(dest) {
+                Ok(vfs) => {
+                    opened = vfs;
+                    opened.as_ref()
+                }
+                Err(why) => return vec![why],
+            }
+        };
+        let same_fs = std::ptr::addr_eq(panel.vfs.as_ref(), dest_fs);
+        let into_dir = dest_fs.stat(dest).is_ok_and(|stat| stat.is_dir());
+
+        panel
+            .targets()
+            .iter()
+            .filter_map(|entry| {
+                let to = match into_dir {
+                    true => dest.join(&entry.file_name),
+                    false => dest.to_path_buf(),
+                };
+                if same_fs && is_inside(dest_fs, &entry.path, &to) {
+                    return Some(anyhow!(
+                        "Cannot copy or move '{}' into itself",
+                        entry.path.display()
+                    ));
+                }
+                match (is_move, same_fs) {
+                    (true, true) if dest_fs.stat(&to).is_ok() => {
+                        Err(anyhow!("File '{}' already exists", to.display()))
+                    }
+                    (true, true) => dest_fs.rename(&entry.path, &to),
+                    (true, false) => vfs::copy(panel.vfs.as_ref(), &entry.path, dest_fs, &to)
+                        .and_then(|_| panel.vfs.remove(&entry.path)),
+                    (false, _) => vfs::copy(panel.vfs.as_ref(), &entry.path, dest_fs, &to),
+                }
+                .err()
+            })
+            .collect()
+    }
+
+    /// Put the marked (or selected) files to the clipboard
+    fn yank(&mut self, mode: ClipboardMode) {
+        let files = self.panel().targets();
+        if files.is_empty() {
+            self.error_text = Some("Nothing to copy or cut".to_string());
+            return;
+        }
+        if !self.check_local("Clipboard") {
+            return;
+        }
+
+        self.clipboard = Some(Clipboard { mode, files });
+        self.panel_mut().marked.clear();
+    }
+
+    fn paste(&mut self) {
+        if !self.check_local("Clipboard") {
+            return;
+        }
+        let Some(clipboard) = &self.clipboard else {
+            self.error_text = Some("Clipboard is empty".to_string());
+            return;
+        };
+
+        let mut paste = Paste::new(clipboard, &self.panel().current_dir);
+        let conflict = paste.run();
+        self.continue_paste(paste, conflict);
+    }
+
+    /// Ask user about the conflict or finish pasting
+    fn continue_paste(&mut self, paste: Paste, conflict: Option<PathBuf>) {
+        if let Some(pth) = conflict {
+            self.paste = Some(paste);
+            self.open_dialog(
+                Dialog::new(DialogKind::Conflict, "File exists")
+                    .text(format!("'{}' already exists", pth.display()))
+                    .checkbox("all", "Apply to all conflicts", false)
+                    .buttons(&["Overwrite", "Skip", "Rename"]),
+            );
+            return;
+        }
+
+        // Вырезанные файлы уже перемещены, повторно вставить их нельзя
+        if self
+            .clipboard
+            .as_ref()
+            .is_some_and(|clipboard| clipboard.mode == ClipboardMode::Cut)
+        {
+            self.clipboard = None;
+        }
+        if let Err(why) = self.rescan_all() {
+            self.error_text = Some(why.to_string());
+        } else {
+            self.set_errors(paste.errors);
+        }
+    }
+
+    /// Copy path (`what = 0`), name (1), parent directory (2) or contents (3)
+    /// of the selected file to the system clipboard
+    fn copy_to_system(&mut self, what: usize) -> Result<()> {
+        let Some(selected) = self.panel().selected.clone() else {
+            return Ok(());
+        };
+        let pth = fs::canonicalize(&selected.path).unwrap_or(selected.path.clone());
+
+        let text = match what {
+            0 => pth.to_string_lossy().to_string(),
+            1 => pth
+                .file_name()
+                .unwrap_or(pth.as_os_str())
+                .to_string_lossy()
+                .to_string(),
+            2 => pth.parent().unwrap_or(&pth).to_string_lossy().to_string(),
+            _ if selected.byte_size > 2_u64.pow(20) => {
+                return Err(anyhow!("File too large (> 1 MBytes)"));
+            }
+            _ => String::from_utf8(self.panel().read(&selected, 2_u64.pow(20))?)
+                .map_err(|_| anyhow!("Failed to read '{}': not a text file", pth.display()))?,
+        };
+
+        system_clipboard::copy(&text)
+            .map_err(|why| anyhow!("Failed to copy to the clipboard: {why}"))
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) {
+        if !self.dialogs.is_empty() {
+            self.handle_dialog_key(key_event);
+            return;
+        }
+        if let Some(output) = &mut self.output {
+            if !output.handle_key(key_event) {
+                self.output = None;
+            }
+            return;
+        }
+
+        // Файлы из архива можно только распаковать, а на удалённом хосте
+        // доступны только копирование, перемещение, переименование и удаление
+        let read_only = self.panel().vfs.is_read_only();
+        let remote = !self.panel().vfs.is_local() && !read_only;
+        match key_event.code {
+            KeyCode::Delete
+            | KeyCode::Char('f' | 'n' | 'l' | 'A' | 'z' | 'X' | 'y' | 'm' | 'P')
+                if remote =>
+            {
+                self.error_text =
+                    Some("Not supported on the remote host (use F5-F8, R and Enter)".to_string());
+            }
+            KeyCode::F(6..=8)
+            | KeyCode::Delete
+            | KeyCode::Char('f' | 'n' | 'l' | 'A' | 'R' | 'z' | 'y' | 'm' | 'P')
+                if read_only =>
+            {
+                self.error_text =
+                    Some("Archive is read-only (use F5 to extract files)".to_string());
+            }
+            KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                if let Some(choose) = self.choose {
+                    if let Err(why) = self.choose(choose) {
+                        self.error_text = Some(why.to_string());
+                    }
                 }
             }
+            KeyCode::F(5) if read_only => self.ask_extract(),
+            KeyCode::Char('X') => self.ask_extract(),
+
+            KeyCode::F(4) => self.edit_targets(),
+            KeyCode::F(5) => self.ask_copy_dest(DialogKind::Copy),
+            KeyCode::F(6) => self.ask_copy_dest(DialogKind::Move),
+            KeyCode::F(7) => self.ask_create_dir(),
+            KeyCode::F(8) => self.remove_targets(true),
+            KeyCode::Delete => self.remove_targets(false),
+            KeyCode::F(10) | KeyCode::Char('q') | KeyCode::Char('й') => {
+                self.exit();
+            }
+
+            KeyCode::Down | KeyCode::Char('j') => self.panel_mut().select_next(),
+            KeyCode::Up | KeyCode::Char('k') => self.panel_mut().select_previous(),
+            KeyCode::Home | KeyCode::Char('H') => self.panel_mut().select_first(),
+            KeyCode::End | KeyCode::Char('L') => self.panel_mut().select_last(),
+            KeyCode::Char('~') => self.cd(get_home()),
+            KeyCode::Char('/') => self.cd("/"),
+            KeyCode::Char('g') => self.ask_go_to(),
+            KeyCode::Char('r') => self.ask_connect(),
+            KeyCode::Char('o') => self.ask_open_with(),
+            KeyCode::Char(':') => self.ask_command(),
             KeyCode::Char('.') => {
                 self.show_hidden = !self.show_hidden;
-                if let Err(why) = self.rescan_dir() {
+                if let Err(why) = self.rescan_all() {
                     self.error_text = Some(why.to_string());
                 }
             }
@@ -216,24 +1756,108 @@ impl F {
                     self.show_preview = false
                 };
             }
+            KeyCode::Char('2') => {
+                self.two_panels = !self.two_panels;
+                if self.two_panels {
+                    // Предпросмотр занимает место второй панели, поэтому
+                    // отключаем его при переходе в двухпанельный режим
+                    self.show_preview = false;
+                    self.show_bytes = false;
+                } else {
+                    self.tab_mut().active = 0;
+                }
+            }
+            KeyCode::Tab if self.two_panels => {
+                let tab = self.tab_mut();
+                tab.active = 1 - tab.active;
+            }
+
+            KeyCode::Char('t') => self.new_tab(),
+            KeyCode::Char('x') => self.close_tab(),
+            KeyCode::Char(']') => self.tab = (self.tab + 1) % self.tabs.len(),
+            KeyCode::Char('[') => self.tab = (self.tab + self.tabs.len() - 1) % self.tabs.len(),
+            KeyCode::Char('}') => self.move_tab(1),
+            KeyCode::Char('{') => self.move_tab(-1),
+
+            KeyCode::Left | KeyCode::Right => {
+                let show_hidden = self.show_hidden;
+                let panel = self.panel_mut();
+                let rslt = if key_event.code == KeyCode::Left {
+                    panel.go_back(show_hidden)
+                } else {
+                    panel.go_forward(show_hidden)
+                };
+                if let Err(why) = rslt {
+                    self.error_text = Some(why.to_string());
+                }
+            }
+
+            KeyCode::Char('s') | KeyCode::Char('S') => {
+                let show_hidden = self.show_hidden;
+                let panel = self.panel_mut();
+                if key_event.code == KeyCode::Char('s') {
+                    panel.sort = panel.sort.next();
+                } else {
+                    panel.reverse = !panel.reverse;
+                }
+                if let Err(why) = panel.rescan_dir(show_hidden) {
+                    self.error_text = Some(why.to_string());
+                }
+            }
+            KeyCode::Char('F') => self.ask_pattern(DialogKind::Filter),
 
             KeyCode::Enter => {
                 self.remove_error_msg();
-                if let Some(selected) = self.selected.clone() {
-                    // Сохраняем путь до предыдущей текущей директории чтобы восстановить его
-                    // в случае ошибки (например, когда не можем зайти в новую директорию)
-                    let old_cur_dir = self.current_dir.clone();
-
-                    if selected.path.is_dir() {
-                        self.current_dir = selected.path;
-                        if let Err(why) = self.rescan_dir() {
-                            self.current_dir = old_cur_dir; // восстанавливаем старый путь
-                            self.error_text = Some(why.to_string());
-                        }
-                    }
+                let show_hidden = self.show_hidden;
+                let Some(selected) = self.panel().selected.clone() else {
+                    return;
+                };
+
+                // Архивы открываются как директории
+                let panel = self.panel();
+                let is_dir = selected.is_parent()
+                    || (panel.vfs.is_local() && Format::from_path(&selected.path).is_some())
+                    || panel
+                        .vfs
+                        .stat(&selected.path)
+                        .is_ok_and(|stat| stat.is_dir());
+                let rslt = if is_dir {
+                    self.panel_mut().open(&selected, show_hidden)
+                } else if self.choose == Some(Choose::Files) {
+                    self.choose(Choose::Files)
+                } else {
+                    self.open_file(0)
+                };
+                if let Err(why) = rslt {
+                    self.error_text = Some(why.to_string());
                 }
             }
 
+            KeyCode::Char('f') => self.ask_find(),
+            KeyCode::Char('n') => self.ask_create_file(),
+            KeyCode::Char('l') => self.ask_create_link(),
+            KeyCode::Char('A') => self.ask_attrs(),
+            KeyCode::Char('R') => self.ask_rename(),
+            KeyCode::Char('z') => self.ask_archive(),
+
+            KeyCode::Char('y') => self.yank(ClipboardMode::Copy),
+            KeyCode::Char('m') => self.yank(ClipboardMode::Cut),
+            KeyCode::Char('P') => self.paste(),
+            KeyCode::Char('C') if self.panel().selected.is_some() => self.open_dialog(
+                Dialog::new(DialogKind::SystemClipboard, "Copy to the clipboard").radio(
+                    "what",
+                    "Copy",
+                    &["Path", "Name", "Parent directory", "Contents"],
+                    0,
+                ),
+            ),
+
+            KeyCode::Char(' ') | KeyCode::Insert => self.panel_mut().toggle_mark(),
+            KeyCode::Char('+') => self.ask_pattern(DialogKind::Mark),
+            KeyCode::Char('-') => self.ask_pattern(DialogKind::Unmark),
+            KeyCode::Char('*') => self.panel_mut().invert_marks(),
+            KeyCode::Char('a') => self.panel_mut().mark_by_pattern("*", true),
+
             KeyCode::Esc => self.remove_error_msg(),
             KeyCode::Char('c') => self.update_colors(),
             _ => {}
@@ -241,14 +1865,38 @@ impl F {
     }
 
     fn update_colors(&mut self) {
-        match Colors::parse("./colors.toml") {
+        match Colors::parse(&self.theme) {
             Ok(colors) => self.colors = colors,
             Err(why) => self.error_text = Some(why.to_string()),
         }
     }
 
     fn keys(&self) -> Line<'_> {
+        if let Some(choose) = self.choose {
+            return Line::from(vec![
+                match choose {
+                    Choose::Files => "Enter/Ctrl+S".bold().red(),
+                    Choose::Dir => "Ctrl+S".bold().red(),
+                },
+                match choose {
+                    Choose::Files => " Choose file (marked files)  ".into(),
+                    Choose::Dir => " Choose directory  ".into(),
+                },
+                "Space".bold().red(),
+                " Mark  ".into(),
+                "q".bold().red(),
+                " Cancel".into(),
+            ])
+            .bg(Color::Gray)
+            .fg(Color::Black);
+        }
         Line::from(vec![
+            "F5".bold().red(),
+            " Copy  ".into(),
+            "F6".bold().red(),
+            " Move  ".into(),
+            "F7".bold().red(),
+            " Mkdir  ".into(),
             "F8".bold().red(),
             " Force delete  ".into(),
             "Del".bold().red(),
@@ -261,6 +1909,14 @@ impl F {
             " Show hidden  ".into(),
             "p".bold().red(),
             " Show preview  ".into(),
+            "f".bold().red(),
+            " Find  ".into(),
+            "2".bold().red(),
+            " Two panels  ".into(),
+            "t".bold().red(),
+            " New tab  ".into(),
+            "y/m/P".bold().red(),
+            " Copy/Cut/Paste  ".into(),
             "q".bold().red(),
             " Quit".into(),
         ])
@@ -268,6 +1924,77 @@ impl F {
         .fg(Color::Black)
     }
 
+    fn tab_bar(&self) -> Line<'_> {
+        let tcols = self.colors.title;
+        let spans = self.tabs.iter().enumerate().map(|(idx, tab)| {
+            let span = Span::from(format!(" {}:{} ", idx + 1, tab.title()));
+            if idx == self.tab {
+                span.bold().reversed()
+            } else {
+                span
+            }
+        });
+
+        Line::from(spans.collect::<Vec<_>>())
+            .fg(color_from_u8(tcols.text).unwrap_or_default())
+            .bg(color_from_u8(tcols.background).unwrap_or_default())
+    }
+
+    /// Number and total size of the marked files and the clipboard contents
+    fn status(&self) -> Option<Line<'_>> {
+        let panel = self.panel();
+        let mut spans = Vec::new();
+
+        if let Some(clipboard) = &self.clipboard {
+            spans.push(Span::from(format!(
+                " Clipboard: {} files ({}) ",
+                clipboard.files.len(),
+                match clipboard.mode {
+                    ClipboardMode::Copy => "copy",
+                    ClipboardMode::Cut => "cut",
+                }
+            )));
+        }
+        if let Some(description) = panel.vfs.description() {
+            spans.push(Span::from(format!(" {description} ")).bold());
+        }
+        if !panel.marked.is_empty() {
+            spans.push(
+                Span::from(format!(
+                    " {} marked, {} ",
+                    panel.marked.len(),
+                    FileSize::get_human_size(panel.marked_size())
+                ))
+                .fg(color_from_u8(self.colors.panels.marked).unwrap_or_default())
+                .bold(),
+            );
+        }
+
+        if spans.is_empty() {
+            None
+        } else {
+            Some(Line::from(spans).fg(Color::Gray).bg(Color::Black))
+        }
+    }
+
+    /// Keys of the opened dialog
+    fn dialog_keys(&self) -> Line<'_> {
+        Line::from(vec![
+            "Tab".bold().red(),
+            " Next field  ".into(),
+            "Shift+Tab".bold().red(),
+            " Previous field  ".into(),
+            "Space".bold().red(),
+            " Toggle  ".into(),
+            "Enter".bold().red(),
+            " OK  ".into(),
+            "Esc".bold().red(),
+            " Cancel".into(),
+        ])
+        .bg(Color::Gray)
+        .fg(Color::Black)
+    }
+
     fn ui(&mut self, frame: &mut Frame) {
         let chunks = Layout::default()
             .direction(Direction::Vertical)
@@ -278,7 +2005,26 @@ impl F {
             ])
             .split(frame.area());
 
-        frame.render_widget(self.keys(), chunks[2]);
+        let footer = match self.status() {
+            Some(summary) => Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Min(0),
+                    Constraint::Length(summary.width() as u16),
+                ])
+                .split(chunks[2]),
+            None => Layout::default()
+                .constraints([Constraint::Min(0)])
+                .split(chunks[2]),
+        };
+        if self.dialogs.is_empty() {
+            frame.render_widget(self.keys(), footer[0]);
+        } else {
+            frame.render_widget(self.dialog_keys(), footer[0]);
+        }
+        if let Some(summary) = self.status() {
+            frame.render_widget(summary, footer[1]);
+        }
 
         let tcols = self.colors.title;
         let title = match &self.error_text {
@@ -292,17 +2038,331 @@ impl F {
                 .fg(Color::Red)
                 .centered(),
         };
-        frame.render_widget(title, chunks[0]);
+        if self.tabs.len() > 1 && self.error_text.is_none() {
+            let title = title.right_aligned();
+            let tab_bar = self.tab_bar();
+            let chunks = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([
+                    Constraint::Min(tab_bar.width() as u16),
+                    Constraint::Length(title.width() as u16),
+                ])
+                .split(chunks[0]);
+
+            frame.render_widget(tab_bar, chunks[0]);
+            frame.render_widget(title, chunks[1]);
+        } else {
+            frame.render_widget(title, chunks[0]);
+        }
 
         let mut ui = FilesView { f: self };
         ui.ui(chunks[1], frame);
+        if let Some(output) = &mut self.output {
+            output.render(chunks[1], frame, &self.colors);
+        }
+
+        for dialog in &self.dialogs {
+            dialog.render(frame.area(), frame, &self.colors);
+        }
     }
 
-    pub fn run(&mut self, term: &mut DefaultTerminal) -> Result<()> {
+    /// Local directory of the active panel shown on exit (the directory of
+    /// the archive for archives, nothing for remote hosts)
+    pub fn last_dir(&self) -> Option<PathBuf> {
+        let dir = &self.panel().current_dir;
+        if vfs::is_url(dir) {
+            return None;
+        }
+        let dir = match vfs::archive_file(dir) {
+            Some(archive) => archive.parent()?,
+            None => dir,
+        };
+        fs::canonicalize(dir).ok()
+    }
+
+    /// Enable the file chooser mode: `f` exits after the files are chosen
+    pub fn chooser(mut self, choose: Choose) -> Self {
+        self.choose = Some(choose);
+        self
+    }
+
+    /// Absolute paths chosen in the file chooser mode (empty if the choice
+    /// was cancelled)
+    pub fn chosen(&self) -> &[PathBuf] {
+        &self.chosen
+    }
+
+    /// Choose the marked files (the selected one if nothing is marked). The
+    /// current directory is chosen in the [`Choose::Dir`] mode if there are
+    /// no marked or selected directories
+    fn choose(&mut self, choose: Choose) -> Result<()> {
+        let panel = self.panel();
+        if !panel.vfs.is_local() {
+            return Err(anyhow!("Only local files can be chosen"));
+        }
+        let mut chosen = panel
+            .targets()
+            .into_iter()
+            .filter(|entry| choose == Choose::Files || entry.path.is_dir())
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>();
+        if chosen.is_empty() {
+            match choose {
+                Choose::Files => return Err(anyhow!("Nothing to choose")),
+                Choose::Dir => chosen.push(panel.current_dir.clone()),
+            }
+        }
+        self.chosen = chosen;
+        self.exit();
+        Ok(())
+    }
+
+    pub fn run(&mut self, term: &mut Terminal) -> Result<()> {
         while !self.is_exit {
             term.draw(|frame| self.ui(frame))?;
-            self.handle_events()?;
+            if self.archiving.is_some() {
+                // Пока архив создаётся, экран обновляется и без нажатий
+                if event::poll(Duration::from_millis(100))? {
+                    self.handle_events()?;
+                }
+                self.update_archiving();
+            } else {
+                self.handle_events()?;
+            }
+
+            if let Some(external) = self.external.take() {
+                term::restore();
+                let rslt = self.run_external(external);
+                *term = term::init()?;
+                if let Err(why) = rslt {
+                    self.error_text = Some(why.to_string());
+                }
+            }
         }
-        Ok(())
+        // Выбор файлов из других программ не должен менять сохранённые вкладки
+        match self.choose {
+            Some(_) => Ok(()),
+            None => self.save_session(),
+        }
+    }
+}
+
+/// Bits of the special permissions and IDs of their checkboxes
+const SPECIAL_BITS: [(&str, u32); 3] = [("suid", 0o4000), ("sgid", 0o2000), ("sticky", 0o1000)];
+
+/// Show the permission bits in the checkboxes of the permissions dialog
+fn set_mode_bits(dialog: &mut Dialog, mode: u32) {
+    let bits = (0..9).map(|n| mode & (0o400 >> n) != 0).collect::<Vec<_>>();
+    dialog.set_grid_checked("perm", &bits);
+    for (id, bit) in SPECIAL_BITS {
+        dialog.set_checked(id, mode & bit != 0);
+    }
+}
+
+fn set_mode(dialog: &mut Dialog, mode: u32) {
+    set_mode_bits(dialog, mode);
+    dialog.set_value("octal", format!("{mode:04o}"));
+}
+
+/// Synchronize the octal field with the checkboxes of the permissions dialog
+fn sync_mode(dialog: &mut Dialog) {
+    if dialog.focused_id() == Some("octal") {
+        if let Ok(mode) = parse_mode(dialog.value("octal")) {
+            set_mode_bits(dialog, mode);
+        }
+        return;
+    }
+
+    let mut mode = dialog
+        .grid_checked("perm")
+        .iter()
+        .enumerate()
+        .filter(|(_, checked)| **checked)
+        .fold(0, |mode, (n, _)| mode | (0o400 >> n));
+    for (id, bit) in SPECIAL_BITS {
+        if dialog.checked(id) {
+            mode |= bit;
+        }
+    }
+    dialog.set_value("octal", format!("{mode:04o}"));
+}
+
+/// Find the tab which shows the directory in the active panel (or the both
+/// directories if the second one is specified). The `active` tab is checked
+/// first
+fn shown_tab(tabs: &[Tab], active: usize, dir: &Path, second_dir: Option<&Path>) -> Option<usize> {
+    let shows = |tab: &Tab| match second_dir {
+        Some(second_dir) => {
+            tab.panels[0].current_dir == dir
+                && tab.panels[1].current_dir == second_dir
+                && tab.panels.iter().all(|panel| panel.search.is_none())
+        }
+        None => {
+            tab.panels[tab.active].current_dir == dir && tab.panels[tab.active].search.is_none()
+        }
+    };
+    match tabs.get(active) {
+        Some(tab) if shows(tab) => Some(active),
+        _ => tabs.iter().position(shows),
+    }
+}
+
+/// Get the absolute path of the directory shown in the panel. Paths inside
+/// of archives don't exist on the disk and addresses of the remote files are
+/// already absolute
+fn absolute_dir(pth: &Path) -> Result<PathBuf> {
+    if vfs::is_url(pth) {
+        return Ok(pth.to_path_buf());
+    }
+    fs::canonicalize(pth)
+        .or_else(|_| std::path::absolute(pth))
+        .map_err(|why| anyhow!("Failed to open '{}': {}", pth.display(), why))
+}
+
+/// Check if `to` is inside of the `from` file or directory (or is the same
+/// file). Paths are compared without links and `..` (the link itself isn't
+/// resolved, because it's copied or moved as the link)
+fn is_inside(fs: &dyn Vfs, from: &Path, to: &Path) -> bool {
+    let real = |pth: &Path| match (pth.parent(), pth.file_name()) {
+        (Some(parent), Some(name)) => fs
+            .canonicalize(parent)
+            .map(|dir| dir.join(name))
+            .unwrap_or(pth.to_path_buf()),
+        _ => fs.canonicalize(pth).unwrap_or(pth.to_path_buf()),
+    };
+
+    real(to).starts_with(real(from))
+}
+
+/// Build the search query (see `crate::find`) from the fields of the find
+/// dialog. Values of the fields are quoted, so they may contain spaces
+fn find_query(dialog: &Dialog) -> String {
+    let mut terms = vec![dialog.value("query").trim().to_string()];
+
+    for (id, prefix) in [
+        ("larger", "size>"),
+        ("smaller", "size<"),
+        ("newer", "newer:"),
+        ("older", "older:"),
+        ("owner", "owner:"),
+        ("group", "group:"),
+        ("perm", "perm:"),
+        ("depth", "depth:"),
+    ] {
+        let val = dialog.value(id).trim();
+        if !val.is_empty() {
+            terms.push(format!("{prefix}{}", quote(val)));
+        }
+    }
+
+    // Первый вариант ("Any") не ограничивает тип файла
+    if let Some(file_type) = ["f", "x", "d", "l", "s"].get(dialog.selected("type").wrapping_sub(1))
+    {
+        terms.push(format!("type:{file_type}"));
+    }
+
+    terms.join(" ").trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use f_core::test_utils::TempDir;
+
+    #[test]
+    fn shown_tab_test() {
+        let dir = TempDir::new("shown_tab");
+        let (a, b) = (dir.join("a"), dir.join("b"));
+        fs::create_dir_all(&a).unwrap();
+        fs::create_dir_all(&b).unwrap();
+
+        let mut tabs = vec![Tab::new(&a, false).unwrap(), Tab::new(&a, false).unwrap()];
+        tabs[1].panels[1] = Panel::new(&b, false).unwrap();
+        tabs[1].active = 1;
+
+        assert_eq!(shown_tab(&tabs, 0, &a, None), Some(0));
+        assert_eq!(shown_tab(&tabs, 0, &b, None), Some(1));
+        assert_eq!(shown_tab(&tabs, 0, &a, Some(&b)), Some(1));
+        assert_eq!(shown_tab(&tabs, 0, &b, Some(&a)), None);
+        assert_eq!(shown_tab(&tabs, 0, &dir, None), None);
+
+        // Активная вкладка прошлой сессии выбирается первой
+        tabs[1].active = 0;
+        assert_eq!(shown_tab(&tabs, 1, &a, None), Some(1));
+        assert_eq!(shown_tab(&tabs, 5, &a, None), Some(0));
+    }
+
+    #[test]
+    fn rescan_test() {
+        let dir = TempDir::new("rescan");
+        for name in ["a", "b", "c"] {
+            fs::write(dir.join(name), name).unwrap();
+        }
+
+        let mut panel = Panel::new(&dir, false).unwrap();
+        panel.select_name("b");
+        fs::write(dir.join("0"), "").unwrap();
+        panel.rescan_dir(false).unwrap();
+        assert_eq!(panel.selected.as_ref().unwrap().file_name, "b");
+
+        // Удалённый файл заменяется файлом на той же строке
+        let idx = panel.idx;
+        fs::remove_file(dir.join("b")).unwrap();
+        panel.rescan_dir(false).unwrap();
+        assert_eq!(panel.idx, idx);
+        assert_eq!(panel.selected.as_ref().unwrap().file_name, "c");
+        fs::remove_file(dir.join("c")).unwrap();
+        panel.rescan_dir(false).unwrap();
+        assert_eq!(panel.selected.as_ref().unwrap().file_name, "a");
+
+        // В другой директории курсор в начале
+        fs::create_dir(dir.join("sub")).unwrap();
+        fs::write(dir.join("sub/x"), "").unwrap();
+        panel.cd(dir.join("sub"), false).unwrap();
+        assert_eq!(panel.idx, Some(0));
+    }
+
+    #[test]
+    fn archive_panel_test() {
+        let dir = TempDir::new("archive_panel");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/a"), "abc").unwrap();
+        let file = dir.join("src.tar");
+        let archive = Archive {
+            format: Format::Tar,
+            level: 0,
+            exclude_hidden: false,
+        };
+        archive
+            .create(&[dir.join("src")], &file, &mut |_| true)
+            .unwrap();
+
+        // Поиск работает только с локальными файлами и не должен искать по
+        // путям архива на диске
+        let mut panel = Panel::new(file.join("src"), false).unwrap();
+        let err = panel.search("a".to_string(), false).unwrap_err();
+        assert!(err.to_string().starts_with("Search is not supported"));
+        assert!(panel.search.is_none());
+
+        let mut panel = Panel::new(&dir, false).unwrap();
+        panel.search("a".to_string(), false).unwrap();
+        assert_eq!(panel.rows.len(), 2);
+    }
+
+    #[test]
+    fn is_inside_test() {
+        let dir = TempDir::new("is_inside");
+        fs::create_dir_all(dir.join("a/sub")).unwrap();
+        std::os::unix::fs::symlink(dir.join("a"), dir.join("link")).unwrap();
+
+        assert!(is_inside(&LocalFs, &dir.join("a"), &dir.join("a/sub/a")));
+        assert!(is_inside(&LocalFs, &dir.join("a"), &dir.join("a/../a")));
+        assert!(is_inside(&LocalFs, &dir.join("a"), &dir.join("link/a")));
+        assert!(!is_inside(&LocalFs, &dir.join("a"), &dir.join("b")));
+
+        // Ссылка копируется как ссылка, поэтому её можно положить в каталог,
+        // на который она указывает
+        assert!(!is_inside(&LocalFs, &dir.join("link"), &dir.join("a/link")));
+        assert!(is_inside(&LocalFs, &dir.join("link"), &dir.join("link")));
     }
 }
@@ -36,7 +36,7 @@
 
 use core::str;
 
-use crate::{traits::Toml, FileEntry, FileType};
+use f_core::{traits::Toml, FileEntry, FileType};
 use ratatui::style::{Color, Style, Stylize};
 use serde::{Deserialize, Serialize};
 
@@ -82,6 +82,10 @@ pub struct Panels {
 
     pub selection_color: u8,
 
+    /// Color of the marked files
+    #[serde(default = "Panels::default_marked")]
+    pub marked: u8,
+
     pub header_bg: u8,
     pub header_fg: u8,
 }
@@ -185,6 +189,12 @@ impl Default for Title {
     }
 }
 
+impl Panels {
+    fn default_marked() -> u8 {
+        93
+    }
+}
+
 impl Default for Panels {
     fn default() -> Self {
         Self {
@@ -209,6 +219,7 @@ impl Default for Panels {
 
             hidden: 38,
             selection_color: 96,
+            marked: Self::default_marked(),
 
             header_bg: 98,
             header_fg: 38,
@@ -233,6 +244,7 @@ pub struct FileColor<'a> {
     pub entry: &'a FileEntry,
     pub cols: Panels,
     pub selected: bool,
+    pub marked: bool,
 }
 
 impl<'a> FileColor<'a> {
@@ -259,7 +271,9 @@ impl<'a> FileColor<'a> {
     }
 
     fn fg(&self) -> Option<Color> {
-        if self.entry.is_hidden {
+        if self.marked {
+            color_from_u8(self.cols.marked)
+        } else if self.entry.is_hidden {
             self.fg_hidden()
         } else {
             self.fg_not_hidden()
@@ -267,6 +281,10 @@ impl<'a> FileColor<'a> {
     }
 
     fn modifier(&self) -> Modifier {
+        if self.marked {
+            return Modifier::Bold;
+        }
+
         Modifier::from(match self.entry.file_type {
             FileType::File | FileType::FileExecutable => self.cols.file_modifier.unwrap_or(8),
             FileType::Directory | FileType::ParentDirectory => self.cols.dir_modifier.unwrap_or(8),
@@ -299,10 +317,16 @@ impl<'a> FileColor<'a> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use f_core::test_utils::TempDir;
 
     #[test]
     fn conf_write_test() {
-        let pth = "./colors.toml";
-        Colors::default().write(pth).unwrap();
+        // Тест не должен перезаписывать поставляемую тему `./colors.toml`
+        let dir = TempDir::new("colors");
+        let pth = dir.join("colors.toml");
+        Colors::default().write(&pth).unwrap();
+        Colors::parse(&pth).unwrap();
+
+        Colors::parse("./colors.toml").unwrap();
     }
 }
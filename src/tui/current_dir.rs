@@ -9,7 +9,7 @@ use ratatui::{
     widgets::{List, ListItem, ListState, StatefulWidget},
 };
 
-use crate::FileEntry;
+use f_core::FileEntry;
 
 pub struct CurrentDir {
     /// Directory entries
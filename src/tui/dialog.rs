@@ -0,0 +1,1108 @@
+//! Modal dialog windows
+//!
+//! Dialog is a list of fields (text labels, input lines, checkboxes and
+//! radio lists) with the row of buttons at the bottom. The last button always
+//! cancels the dialog. Keys:
+//!
+//! - <kbd>Tab</kbd>, <kbd>Shift+Tab</kbd> - move focus to the next/previous
+//!   field or button (in the path inputs - complete the directory name and
+//!   cycle through the completions);
+//! - <kbd>Enter</kbd> - press the focused button (or the first button if
+//!   the focus is on the field);
+//! - <kbd>Esc</kbd> - cancel;
+//! - <kbd>Space</kbd> - toggle checkbox;
+//! - <kbd>↑</kbd>, <kbd>↓</kbd> - select option in the radio list or the
+//!   previous text from the input history;
+//! - <kbd>←</kbd>, <kbd>→</kbd> or the first letter - select option in the
+//!   select field;
+//! - <kbd>←</kbd>, <kbd>→</kbd>, <kbd>Home</kbd>, <kbd>End</kbd>,
+//!   <kbd>Backspace</kbd>, <kbd>Del</kbd>, <kbd>Ctrl+U</kbd> - edit text.
+
+use super::colors::{color_from_u8, Colors};
+use f_core::utils::complete_dir;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Color, Style, Stylize},
+    symbols::border,
+    text::{Line, Span},
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+use std::{collections::HashMap, path::PathBuf};
+
+/// What the dialog results will be used for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DialogKind {
+    /// Search files (see `crate::find`)
+    Find,
+
+    /// Mark files by pattern
+    Mark,
+
+    /// Unmark files by pattern
+    Unmark,
+
+    /// Filter files in the listing by pattern
+    Filter,
+
+    /// Copy files to the entered path
+    Copy,
+
+    /// Move files to the entered path
+    Move,
+
+    /// Pasted file already exists
+    Conflict,
+
+    /// Copy something to the system clipboard
+    SystemClipboard,
+
+    /// Go to the entered path
+    GoTo,
+
+    /// Create new file
+    CreateFile,
+
+    /// Create new directory
+    CreateDir,
+
+    /// Create directory despite the warnings about its name
+    ConfirmCreateDir,
+
+    /// Create link to the selected file
+    CreateLink,
+
+    /// Change permissions and owner of the files
+    Attributes,
+
+    /// Rename marked files by the rules
+    Rename,
+
+    /// Create archive from the marked files
+    Archive,
+
+    /// Progress of the archive creation
+    ArchiveProgress,
+
+    /// Extract files from the archive
+    Extract,
+
+    /// Connect to the remote host
+    Connect,
+
+    /// Choose the program for opening the file
+    OpenWith,
+
+    /// Shell command
+    Command,
+
+    /// List of errors
+    Errors,
+}
+
+/// Line of the text input with cursor and history
+#[derive(Debug, Clone, Default)]
+pub struct Input {
+    pub text: String,
+
+    /// Cursor position (in characters)
+    cursor: usize,
+
+    /// Previously entered texts (the latest is the last)
+    history: Vec<String>,
+    history_idx: Option<usize>,
+
+    /// Directory for completing the relative paths (`None` if the input
+    /// isn't a path)
+    complete_base: Option<PathBuf>,
+    /// Completions shown in the popup and index of the selected one
+    completions: Vec<String>,
+    completion_idx: usize,
+}
+
+impl Input {
+    pub fn new<T: ToString>(text: T) -> Self {
+        let text = text.to_string();
+        Self {
+            cursor: text.chars().count(),
+            text,
+            ..Default::default()
+        }
+    }
+
+    fn byte_idx(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map(|(idx, _)| idx)
+            .unwrap_or(self.text.len())
+    }
+
+    fn set_text(&mut self, text: String) {
+        self.cursor = text.chars().count();
+        self.text = text;
+    }
+
+    /// Complete the directory name or select the next (previous if `back`)
+    /// completion
+    fn complete(&mut self, back: bool) {
+        if self.completions.is_empty() {
+            let Some(base) = &self.complete_base else {
+                return;
+            };
+            self.completions = complete_dir(&self.text, base);
+            self.completion_idx = if back {
+                self.completions.len().saturating_sub(1)
+            } else {
+                0
+            };
+        } else if back {
+            self.completion_idx =
+                (self.completion_idx + self.completions.len() - 1) % self.completions.len();
+        } else {
+            self.completion_idx = (self.completion_idx + 1) % self.completions.len();
+        }
+
+        if let Some(text) = self.completions.get(self.completion_idx) {
+            self.set_text(text.clone());
+        }
+        // Единственный вариант сразу принимается, чтобы следующий <Tab>
+        // дополнял уже вложенную директорию
+        if self.completions.len() == 1 {
+            self.completions.clear();
+        }
+    }
+
+    fn history_prev(&mut self) {
+        let idx = match self.history_idx {
+            Some(0) => return,
+            Some(idx) => idx - 1,
+            None if self.history.is_empty() => return,
+            None => self.history.len() - 1,
+        };
+        self.history_idx = Some(idx);
+        self.set_text(self.history[idx].clone());
+    }
+
+    fn history_next(&mut self) {
+        match self.history_idx {
+            Some(idx) if idx + 1 < self.history.len() => {
+                self.history_idx = Some(idx + 1);
+                self.set_text(self.history[idx + 1].clone());
+            }
+            Some(_) => {
+                self.history_idx = None;
+                self.set_text(String::new());
+            }
+            None => {}
+        }
+    }
+
+    pub fn handle_key(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Char('u') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.set_text(String::new());
+            }
+            // Не назначенные сочетания с Ctrl и Alt не вводят символы
+            KeyCode::Char(_)
+                if key_event
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) => {}
+            KeyCode::Char(c) => {
+                let idx = self.byte_idx(self.cursor);
+                self.text.insert(idx, c);
+                self.cursor += 1;
+            }
+            KeyCode::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                let idx = self.byte_idx(self.cursor);
+                self.text.remove(idx);
+            }
+            KeyCode::Delete if self.cursor < self.text.chars().count() => {
+                let idx = self.byte_idx(self.cursor);
+                self.text.remove(idx);
+            }
+            KeyCode::Left => self.cursor = self.cursor.saturating_sub(1),
+            KeyCode::Right => self.cursor = (self.cursor + 1).min(self.text.chars().count()),
+            KeyCode::Home => self.cursor = 0,
+            KeyCode::End => self.cursor = self.text.chars().count(),
+            KeyCode::Up => self.history_prev(),
+            KeyCode::Down => self.history_next(),
+            _ => {}
+        }
+    }
+
+    /// Get the visible part of the text with cursor for the field of `width`
+    /// characters
+    fn spans(&self, width: usize, focused: bool) -> Vec<Span<'_>> {
+        let chars = self.text.chars().collect::<Vec<_>>();
+        let width = width.max(1);
+        // Прокручиваем текст так, чтобы курсор всегда был виден
+        let start = (self.cursor + 1).saturating_sub(width);
+        let end = (start + width).min(chars.len());
+
+        let mut spans = Vec::new();
+        for (idx, c) in chars.iter().enumerate().take(end).skip(start) {
+            let span = Span::from(c.to_string());
+            spans.push(if focused && idx == self.cursor {
+                span.reversed()
+            } else {
+                span
+            });
+        }
+        if self.cursor >= end {
+            spans.push(if focused { " ".reversed() } else { " ".into() });
+        }
+        let len = end - start + usize::from(self.cursor >= end);
+        spans.push(" ".repeat(width.saturating_sub(len)).into());
+
+        spans
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Widget {
+    /// Static text (can't be focused)
+    Text,
+
+    Input(Input),
+    Checkbox(bool),
+
+    /// Options and index of the selected one
+    Radio(Vec<String>, usize),
+
+    /// One line with the selected option (for the long lists of options)
+    Select(Vec<String>, usize),
+
+    /// Table of checkboxes
+    Grid {
+        rows: Vec<String>,
+        cols: Vec<String>,
+
+        /// States of the checkboxes (row by row)
+        checked: Vec<bool>,
+        cursor: usize,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Field {
+    /// Identifier for getting the field value
+    pub id: &'static str,
+    pub label: String,
+    pub widget: Widget,
+}
+
+impl Field {
+    fn is_focusable(&self) -> bool {
+        !matches!(self.widget, Widget::Text)
+    }
+
+    fn height(&self) -> usize {
+        match &self.widget {
+            Widget::Text => self.label.lines().count().max(1),
+            Widget::Radio(options, _) => options.len() + 1,
+            Widget::Grid { rows, .. } => rows.len() + 1,
+            _ => 1,
+        }
+    }
+}
+
+/// Result of the key press handling
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DialogResult {
+    /// Dialog is still opened
+    None,
+
+    /// User pressed <Esc> or the "Cancel" button
+    Cancel,
+
+    /// User pressed the button with this index
+    Button(usize),
+}
+
+#[derive(Clone)]
+pub struct Dialog {
+    pub kind: DialogKind,
+    pub title: String,
+    pub fields: Vec<Field>,
+
+    /// Buttons except the "Cancel" one
+    pub buttons: Vec<String>,
+    /// Label of the last button cancelling the dialog
+    pub cancel: String,
+
+    /// Focused element: index in `fields` or (if it's greater) in `buttons`
+    focus: usize,
+
+    /// Error shown inside the dialog (e.g. about the wrong entered data)
+    pub error: Option<String>,
+}
+
+impl Dialog {
+    /// Create empty dialog with "OK" and "Cancel" buttons
+    pub fn new<T: ToString>(kind: DialogKind, title: T) -> Self {
+        Self {
+            kind,
+            title: title.to_string(),
+            fields: Vec::new(),
+            buttons: vec!["OK".to_string()],
+            cancel: "Cancel".to_string(),
+            focus: 0,
+            error: None,
+        }
+    }
+
+    fn field(mut self, id: &'static str, label: &str, widget: Widget) -> Self {
+        self.fields.push(Field {
+            id,
+            label: label.to_string(),
+            widget,
+        });
+        // Фокус должен быть на первом поле, которое может его получить
+        self.focus = self
+            .fields
+            .iter()
+            .position(|field| field.is_focusable())
+            .unwrap_or(self.fields.len());
+        self
+    }
+
+    pub fn text<T: ToString>(self, text: T) -> Self {
+        self.field("", &text.to_string(), Widget::Text)
+    }
+
+    /// Static text which can be changed later by `set_text()`
+    pub fn text_with_id<T: ToString>(self, id: &'static str, text: T) -> Self {
+        self.field(id, &text.to_string(), Widget::Text)
+    }
+
+    pub fn set_text<T: ToString>(&mut self, id: &str, text: T) {
+        if let Some(field) = self.fields.iter_mut().find(|field| field.id == id) {
+            field.label = text.to_string();
+        }
+    }
+
+    pub fn input<T: ToString>(self, id: &'static str, label: &str, text: T) -> Self {
+        self.field(id, label, Widget::Input(Input::new(text)))
+    }
+
+    /// Input of the path with completion of the directory names. Relative
+    /// paths are completed in the `base` directory
+    pub fn path_input<T: ToString, P: Into<PathBuf>>(
+        self,
+        id: &'static str,
+        label: &str,
+        text: T,
+        base: P,
+    ) -> Self {
+        let input = Input {
+            complete_base: Some(base.into()),
+            ..Input::new(text)
+        };
+        self.field(id, label, Widget::Input(input))
+    }
+
+    pub fn checkbox(self, id: &'static str, label: &str, checked: bool) -> Self {
+        self.field(id, label, Widget::Checkbox(checked))
+    }
+
+    pub fn radio(self, id: &'static str, label: &str, options: &[&str], selected: usize) -> Self {
+        let options = options.iter().map(|opt| opt.to_string()).collect();
+        self.field(id, label, Widget::Radio(options, selected))
+    }
+
+    pub fn select(
+        self,
+        id: &'static str,
+        label: &str,
+        options: &[String],
+        selected: usize,
+    ) -> Self {
+        self.field(id, label, Widget::Select(options.to_vec(), selected))
+    }
+
+    /// Table of checkboxes with `rows` and `cols` labels. `checked` contains
+    /// states of the checkboxes row by row
+    pub fn grid(
+        self,
+        id: &'static str,
+        label: &str,
+        rows: &[&str],
+        cols: &[&str],
+        checked: &[bool],
+    ) -> Self {
+        let widget = Widget::Grid {
+            rows: rows.iter().map(|row| row.to_string()).collect(),
+            cols: cols.iter().map(|col| col.to_string()).collect(),
+            checked: checked.to_vec(),
+            cursor: 0,
+        };
+        self.field(id, label, widget)
+    }
+
+    /// Replace "OK" button with the another ones
+    pub fn buttons(mut self, buttons: &[&str]) -> Self {
+        self.buttons = buttons.iter().map(|btn| btn.to_string()).collect();
+        self
+    }
+
+    /// Set label of the "Cancel" button
+    pub fn cancel_label(mut self, label: &str) -> Self {
+        self.cancel = label.to_string();
+        self
+    }
+
+    /// Fill the inputs history
+    pub fn with_history(mut self, history: &HashMap<&'static str, Vec<String>>) -> Self {
+        for field in &mut self.fields {
+            if let (Widget::Input(input), Some(hist)) = (&mut field.widget, history.get(field.id)) {
+                input.history = hist.clone();
+            }
+        }
+        self
+    }
+
+    /// Save texts of the inputs to the history
+    pub fn save_history(&self, history: &mut HashMap<&'static str, Vec<String>>) {
+        for field in &self.fields {
+            if let Widget::Input(input) = &field.widget {
+                if input.text.is_empty() {
+                    continue;
+                }
+                let hist = history.entry(field.id).or_default();
+                hist.retain(|text| text != &input.text);
+                hist.push(input.text.clone());
+            }
+        }
+    }
+
+    fn get(&self, id: &str) -> Option<&Widget> {
+        self.fields
+            .iter()
+            .find(|field| field.id == id)
+            .map(|field| &field.widget)
+    }
+
+    fn get_mut(&mut self, id: &str) -> Option<&mut Widget> {
+        self.fields
+            .iter_mut()
+            .find(|field| field.id == id)
+            .map(|field| &mut field.widget)
+    }
+
+    /// ID of the focused field
+    pub fn focused_id(&self) -> Option<&'static str> {
+        self.fields.get(self.focus).map(|field| field.id)
+    }
+
+    /// Text of the input field
+    pub fn value(&self, id: &str) -> &str {
+        match self.get(id) {
+            Some(Widget::Input(input)) => &input.text,
+            _ => "",
+        }
+    }
+
+    pub fn set_value<T: ToString>(&mut self, id: &str, text: T) {
+        if let Some(Widget::Input(input)) = self.get_mut(id) {
+            input.set_text(text.to_string());
+        }
+    }
+
+    /// State of the checkbox
+    pub fn checked(&self, id: &str) -> bool {
+        matches!(self.get(id), Some(Widget::Checkbox(true)))
+    }
+
+    pub fn set_checked(&mut self, id: &str, state: bool) {
+        if let Some(Widget::Checkbox(checked)) = self.get_mut(id) {
+            *checked = state;
+        }
+    }
+
+    /// Index of the selected option in the radio list or select field
+    pub fn selected(&self, id: &str) -> usize {
+        match self.get(id) {
+            Some(Widget::Radio(_, selected) | Widget::Select(_, selected)) => *selected,
+            _ => 0,
+        }
+    }
+
+    /// States of the checkboxes in the table (row by row)
+    pub fn grid_checked(&self, id: &str) -> Vec<bool> {
+        match self.get(id) {
+            Some(Widget::Grid { checked, .. }) => checked.clone(),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn set_grid_checked(&mut self, id: &str, states: &[bool]) {
+        if let Some(Widget::Grid { checked, .. }) = self.get_mut(id) {
+            checked.copy_from_slice(states);
+        }
+    }
+
+    /// Number of the focusable elements (fields and buttons with "Cancel")
+    fn focus_len(&self) -> usize {
+        self.fields.len() + self.buttons.len() + 1
+    }
+
+    fn is_focusable(&self, idx: usize) -> bool {
+        self.fields
+            .get(idx)
+            .is_none_or(|field| field.is_focusable())
+    }
+
+    fn focus_next(&mut self) {
+        loop {
+            self.focus = (self.focus + 1) % self.focus_len();
+            if self.is_focusable(self.focus) {
+                break;
+            }
+        }
+    }
+
+    fn focus_prev(&mut self) {
+        loop {
+            self.focus = (self.focus + self.focus_len() - 1) % self.focus_len();
+            if self.is_focusable(self.focus) {
+                break;
+            }
+        }
+    }
+
+    /// Result of pressing the button with index `idx`
+    fn press(&self, idx: usize) -> DialogResult {
+        if idx < self.buttons.len() {
+            DialogResult::Button(idx)
+        } else {
+            DialogResult::Cancel
+        }
+    }
+
+    pub fn handle_key(&mut self, key_event: KeyEvent) -> DialogResult {
+        self.error = None;
+
+        if let Some(Widget::Input(input)) = self.fields.get_mut(self.focus).map(|f| &mut f.widget) {
+            let is_tab = matches!(key_event.code, KeyCode::Tab | KeyCode::BackTab);
+            if is_tab && input.complete_base.is_some() {
+                input.complete(key_event.code == KeyCode::BackTab);
+                return DialogResult::None;
+            }
+            if !input.completions.is_empty() {
+                input.completions.clear();
+                // <Esc> закрывает только список вариантов
+                if key_event.code == KeyCode::Esc {
+                    return DialogResult::None;
+                }
+            }
+        }
+
+        match key_event.code {
+            KeyCode::Esc => return DialogResult::Cancel,
+            KeyCode::Tab => self.focus_next(),
+            KeyCode::BackTab => self.focus_prev(),
+            KeyCode::Enter => {
+                return self.press(self.focus.saturating_sub(self.fields.len()));
+            }
+            _ => {}
+        }
+
+        let Some(field) = self.fields.get_mut(self.focus) else {
+            // Фокус на кнопках
+            match key_event.code {
+                KeyCode::Left => self.focus_prev(),
+                KeyCode::Right => self.focus_next(),
+                KeyCode::Char(' ') => return self.press(self.focus - self.fields.len()),
+                _ => {}
+            }
+            return DialogResult::None;
+        };
+
+        match (&mut field.widget, key_event.code) {
+            (Widget::Input(input), code) if code != KeyCode::Tab && code != KeyCode::BackTab => {
+                input.handle_key(key_event)
+            }
+            (Widget::Checkbox(checked), KeyCode::Char(' ')) => *checked = !*checked,
+            (Widget::Radio(_, selected), KeyCode::Up | KeyCode::Left) => {
+                *selected = selected.saturating_sub(1)
+            }
+            (Widget::Radio(options, selected), KeyCode::Down | KeyCode::Right) => {
+                *selected = (*selected + 1).min(options.len().saturating_sub(1))
+            }
+            (Widget::Select(_, selected), KeyCode::Left) => *selected = selected.saturating_sub(1),
+            (Widget::Select(options, selected), KeyCode::Right) => {
+                *selected = (*selected + 1).min(options.len().saturating_sub(1))
+            }
+            (Widget::Select(_, selected), KeyCode::Home) => *selected = 0,
+            (Widget::Select(options, selected), KeyCode::End) => {
+                *selected = options.len().saturating_sub(1)
+            }
+            (Widget::Select(options, selected), KeyCode::Char(c))
+                if !key_event
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                // Переход к следующему варианту, начинающемуся с этой буквы
+                if let Some(idx) = (1..=options.len())
+                    .map(|n| (*selected + n) % options.len())
+                    .find(|idx| options[*idx].starts_with(c))
+                {
+                    *selected = idx;
+                }
+            }
+            (
+                Widget::Grid {
+                    checked, cursor, ..
+                },
+                KeyCode::Char(' '),
+            ) => checked[*cursor] = !checked[*cursor],
+            (Widget::Grid { cols, cursor, .. }, KeyCode::Left) if *cursor % cols.len() > 0 => {
+                *cursor -= 1
+            }
+            (Widget::Grid { cols, cursor, .. }, KeyCode::Right)
+                if *cursor % cols.len() + 1 < cols.len() =>
+            {
+                *cursor += 1
+            }
+            (Widget::Grid { cols, cursor, .. }, KeyCode::Up) if *cursor >= cols.len() => {
+                *cursor -= cols.len()
+            }
+            (
+                Widget::Grid {
+                    checked,
+                    cols,
+                    cursor,
+                    ..
+                },
+                KeyCode::Down,
+            ) if *cursor + cols.len() < checked.len() => *cursor += cols.len(),
+            (_, KeyCode::Down) => self.focus_next(),
+            (_, KeyCode::Up) => self.focus_prev(),
+            _ => {}
+        }
+
+        DialogResult::None
+    }
+
+    fn label_width(&self) -> usize {
+        self.fields
+            .iter()
+            .filter(|field| matches!(field.widget, Widget::Input(_) | Widget::Select(..)))
+            .map(|field| field.label.chars().count())
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn lines(&self, width: usize, colors: &Colors) -> Vec<Line<'_>> {
+        let label_width = self.label_width();
+        let focus_style =
+            Style::default().bg(color_from_u8(colors.panels.selection_color).unwrap_or_default());
+        let mut lines = Vec::new();
+
+        for (idx, field) in self.fields.iter().enumerate() {
+            let focused = idx == self.focus;
+            match &field.widget {
+                Widget::Text => lines.extend(
+                    field
+                        .label
+                        .lines()
+                        .map(|line| Line::from(format!(" {line}"))),
+                ),
+                Widget::Input(input) => {
+                    let mut spans = vec![Span::from(format!(" {:>label_width$}: ", field.label))];
+                    spans.extend(
+                        input
+                            .spans(width.saturating_sub(label_width + 4), focused)
+                            .into_iter()
+                            .map(|span| span.underlined()),
+                    );
+                    lines.push(Line::from(spans));
+                }
+                Widget::Checkbox(checked) => {
+                    let line = Line::from(format!(
+                        " [{}] {}",
+                        if *checked { "x" } else { " " },
+                        field.label
+                    ));
+                    lines.push(if focused {
+                        line.style(focus_style)
+                    } else {
+                        line
+                    });
+                }
+                Widget::Select(options, selected) => {
+                    // Список может быть пустым (например, если `/etc/group` не
+                    // читается)
+                    let value = Span::from(format!(
+                        "< {} >",
+                        options.get(*selected).map_or("", |opt| opt.as_str())
+                    ));
+                    lines.push(Line::from(vec![
+                        Span::from(format!(" {:>label_width$}: ", field.label)),
+                        if focused {
+                            value.style(focus_style)
+                        } else {
+                            value
+                        },
+                    ]));
+                }
+                Widget::Grid {
+                    rows,
+                    cols,
+                    checked,
+                    cursor,
+                } => {
+                    let prefix = rows.iter().map(|row| row.chars().count() + 3).max();
+                    let prefix = prefix.unwrap_or(0).max(field.label.chars().count() + 2);
+
+                    let mut header = format!(" {:<prefix$}", format!("{}:", field.label));
+                    for col in cols {
+                        header.push_str(&format!(" {col}  "));
+                    }
+                    lines.push(Line::from(header));
+
+                    for (row_idx, row) in rows.iter().enumerate() {
+                        let mut spans =
+                            vec![Span::from(format!(" {:<prefix$}", format!("  {row}")))];
+                        for col_idx in 0..cols.len() {
+                            let idx = row_idx * cols.len() + col_idx;
+                            let cell = Span::from(if checked[idx] { "[x]" } else { "[ ]" });
+                            spans.push(if focused && idx == *cursor {
+                                cell.style(focus_style)
+                            } else {
+                                cell
+                            });
+                            spans.push(Span::from(" "));
+                        }
+                        lines.push(Line::from(spans));
+                    }
+                }
+                Widget::Radio(options, selected) => {
+                    lines.push(Line::from(format!(" {}:", field.label)));
+                    for (opt_idx, opt) in options.iter().enumerate() {
+                        let line = Line::from(format!(
+                            "   ({}) {}",
+                            if opt_idx == *selected { "•" } else { " " },
+                            opt
+                        ));
+                        lines.push(if focused && opt_idx == *selected {
+                            line.style(focus_style)
+                        } else {
+                            line
+                        });
+                    }
+                }
+            }
+        }
+
+        match self.error_lines(width) {
+            errors if errors.is_empty() => lines.push(Line::default()),
+            errors => lines.extend(
+                errors
+                    .into_iter()
+                    .map(|line| Line::from(line).fg(Color::Red).bold()),
+            ),
+        }
+        let buttons = self
+            .buttons
+            .iter()
+            .map(|btn| btn.as_str())
+            .chain([self.cancel.as_str()])
+            .enumerate()
+            .flat_map(|(idx, btn)| {
+                let span = Span::from(format!("[ {btn} ]"));
+                let span = if self.fields.len() + idx == self.focus {
+                    span.style(focus_style).bold()
+                } else {
+                    span
+                };
+                [span, Span::from("  ")]
+            })
+            .collect::<Vec<_>>();
+        lines.push(Line::from(buttons).centered());
+
+        lines
+    }
+
+    /// Error text split into the lines of `width` characters
+    fn error_lines(&self, width: usize) -> Vec<String> {
+        let Some(error) = &self.error else {
+            return Vec::new();
+        };
+        let chars = error.chars().collect::<Vec<_>>();
+        chars
+            .chunks(width.saturating_sub(2).max(1))
+            .map(|chunk| format!(" {}", chunk.iter().collect::<String>()))
+            .collect()
+    }
+
+    pub fn render(&self, area: Rect, frame: &mut Frame, colors: &Colors) {
+        let width = area.width.saturating_sub(4).clamp(20, 72);
+        let height = self
+            .fields
+            .iter()
+            .map(|field| field.height())
+            .sum::<usize>()
+            + self.error_lines(width as usize - 2).len().max(1)
+            + 3;
+
+        let [area] = Layout::horizontal([Constraint::Length(width)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Length(height as u16)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let block = Block::bordered()
+            .border_set(border::DOUBLE)
+            .title(Line::from(format!(" {} ", self.title)).centered().bold())
+            .fg(color_from_u8(colors.panels.file).unwrap_or_default())
+            .bg(color_from_u8(colors.panels.background).unwrap_or_default())
+            .border_style(
+                Style::default().fg(color_from_u8(colors.panels.border_active).unwrap_or_default()),
+            );
+
+        let lines = self.lines(width as usize - 2, colors);
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+
+        self.render_completions(area, frame, colors);
+    }
+
+    /// Popup with the completions under the focused input
+    fn render_completions(&self, area: Rect, frame: &mut Frame, colors: &Colors) {
+        let Some(Widget::Input(input)) = self.fields.get(self.focus).map(|f| &f.widget) else {
+            return;
+        };
+        if input.completions.is_empty() {
+            return;
+        }
+
+        let row = self.fields[..self.focus]
+            .iter()
+            .map(|field| field.height())
+            .sum::<usize>() as u16;
+        let x = area.x + self.label_width() as u16 + 4;
+        let y = area.y + row + 2;
+        let screen = frame.area();
+        let max_height = screen.height.saturating_sub(y).min(10);
+        if max_height < 3 {
+            return;
+        }
+
+        // В списке показываются только имена директорий без пути
+        let name = |text: &str| {
+            let name = text.trim_end_matches('/');
+            format!("{}/", name.rsplit('/').next().unwrap_or(name))
+        };
+        let width = input
+            .completions
+            .iter()
+            .map(|text| name(text).chars().count() as u16 + 2)
+            .max()
+            .unwrap_or(0)
+            .min(screen.width.saturating_sub(x));
+        let height = (input.completions.len() as u16 + 2).min(max_height);
+        let popup = Rect::new(x, y, width, height);
+
+        // Прокручиваем список так, чтобы выбранный вариант был виден
+        let visible = height as usize - 2;
+        let start = (input.completion_idx + 1).saturating_sub(visible);
+        let focus_style =
+            Style::default().bg(color_from_u8(colors.panels.selection_color).unwrap_or_default());
+        let lines = input
+            .completions
+            .iter()
+            .enumerate()
+            .skip(start)
+            .take(visible)
+            .map(|(idx, text)| {
+                let line = Line::from(name(text));
+                if idx == input.completion_idx {
+                    line.style(focus_style)
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let block = Block::bordered()
+            .fg(color_from_u8(colors.panels.file).unwrap_or_default())
+            .bg(color_from_u8(colors.panels.background).unwrap_or_default());
+        frame.render_widget(Clear, popup);
+        frame.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use f_core::test_utils::TempDir;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    fn type_text(input: &mut Input, text: &str) {
+        for c in text.chars() {
+            input.handle_key(key(KeyCode::Char(c)));
+        }
+    }
+
+    #[test]
+    fn input_edit_test() {
+        let mut input = Input::new("файл");
+        assert_eq!(input.cursor, 4);
+
+        input.handle_key(key(KeyCode::Left));
+        input.handle_key(key(KeyCode::Backspace));
+        type_text(&mut input, "ü");
+        assert_eq!(input.text, "фаüл");
+        input.handle_key(key(KeyCode::Delete));
+        assert_eq!(input.text, "фаü");
+        input.handle_key(key(KeyCode::Delete));
+        assert_eq!(input.text, "фаü");
+
+        input.handle_key(key(KeyCode::Home));
+        input.handle_key(key(KeyCode::Backspace));
+        type_text(&mut input, "1");
+        assert_eq!(input.text, "1фаü");
+        input.handle_key(key(KeyCode::End));
+        input.handle_key(key(KeyCode::Right));
+        type_text(&mut input, "2");
+        assert_eq!(input.text, "1фаü2");
+
+        input.handle_key(ctrl('x'));
+        input.handle_key(KeyEvent::new(KeyCode::Char('b'), KeyModifiers::ALT));
+        assert_eq!(input.text, "1фаü2");
+        input.handle_key(ctrl('u'));
+        assert_eq!((input.text.as_str(), input.cursor), ("", 0));
+    }
+
+    #[test]
+    fn input_history_test() {
+        let mut input = Input {
+            history: vec!["a".to_string(), "b".to_string()],
+            ..Input::new("")
+        };
+
+        input.handle_key(key(KeyCode::Down));
+        assert_eq!(input.text, "");
+        input.handle_key(key(KeyCode::Up));
+        assert_eq!(input.text, "b");
+        input.handle_key(key(KeyCode::Up));
+        input.handle_key(key(KeyCode::Up));
+        assert_eq!((input.text.as_str(), input.cursor), ("a", 1));
+        input.handle_key(key(KeyCode::Down));
+        assert_eq!(input.text, "b");
+        input.handle_key(key(KeyCode::Down));
+        assert_eq!((input.text.as_str(), input.history_idx), ("", None));
+
+        let mut dialog = Dialog::new(DialogKind::Find, "")
+            .input("query", "", "c")
+            .with_history(&HashMap::from([("query", input.history)]));
+        let mut history = HashMap::new();
+        dialog.save_history(&mut history);
+        dialog.handle_key(key(KeyCode::Up));
+        assert_eq!(dialog.value("query"), "b");
+        dialog.save_history(&mut history);
+        assert_eq!(history["query"], ["c", "b"]);
+    }
+
+    #[test]
+    fn complete_test() {
+        let dir = TempDir::new("dialog_complete");
+        for sub in ["ab/c", "ad"] {
+            std::fs::create_dir_all(dir.join(sub)).unwrap();
+        }
+        let mut dialog =
+            Dialog::new(DialogKind::GoTo, "").path_input("path", "", "a", dir.to_path_buf());
+
+        dialog.handle_key(key(KeyCode::Tab));
+        assert_eq!(dialog.value("path"), "ab/");
+        dialog.handle_key(key(KeyCode::Tab));
+        assert_eq!(dialog.value("path"), "ad/");
+        dialog.handle_key(key(KeyCode::BackTab));
+        assert_eq!(dialog.value("path"), "ab/");
+
+        // <Esc> закрывает список вариантов, но не диалог
+        assert_eq!(dialog.handle_key(key(KeyCode::Esc)), DialogResult::None);
+        assert_eq!(dialog.focused_id(), Some("path"));
+
+        // Единственный вариант принимается сразу
+        dialog.handle_key(key(KeyCode::Tab));
+        assert_eq!(dialog.value("path"), "ab/c/");
+        dialog.handle_key(key(KeyCode::Tab));
+        assert_eq!(dialog.value("path"), "ab/c/");
+        assert_eq!(dialog.handle_key(key(KeyCode::Esc)), DialogResult::Cancel);
+    }
+
+    #[test]
+    fn focus_test() {
+        let mut dialog = Dialog::new(DialogKind::CreateFile, "")
+            .text("Text")
+            .input("name", "", "")
+            .text("Text")
+            .checkbox("check", "", false)
+            .select("select", "", &["one".to_string(), "two".to_string()], 0)
+            .buttons(&["Create", "Edit"]);
+        assert_eq!(dialog.focused_id(), Some("name"));
+
+        dialog.handle_key(key(KeyCode::Tab));
+        assert_eq!(dialog.focused_id(), Some("check"));
+        dialog.handle_key(key(KeyCode::Char(' ')));
+        assert!(dialog.checked("check"));
+        dialog.handle_key(key(KeyCode::Down));
+        dialog.handle_key(key(KeyCode::Char('t')));
+        dialog.handle_key(ctrl('o'));
+        assert_eq!(dialog.selected("select"), 1);
+
+        // Фокус на кнопках: "Create", "Edit" и "Cancel"
+        dialog.handle_key(key(KeyCode::Tab));
+        assert_eq!(dialog.focused_id(), None);
+        dialog.handle_key(key(KeyCode::Right));
+        assert_eq!(
+            dialog.handle_key(key(KeyCode::Enter)),
+            DialogResult::Button(1)
+        );
+        dialog.handle_key(key(KeyCode::Right));
+        assert_eq!(
+            dialog.handle_key(key(KeyCode::Char(' '))),
+            DialogResult::Cancel
+        );
+        dialog.handle_key(key(KeyCode::Right));
+        assert_eq!(dialog.focused_id(), Some("name"));
+        dialog.handle_key(key(KeyCode::BackTab));
+        assert_eq!(dialog.focused_id(), None);
+        dialog.handle_key(key(KeyCode::BackTab));
+        dialog.handle_key(key(KeyCode::BackTab));
+        dialog.handle_key(key(KeyCode::BackTab));
+        assert_eq!(dialog.focused_id(), Some("select"));
+        assert_eq!(
+            dialog.handle_key(key(KeyCode::Enter)),
+            DialogResult::Button(0)
+        );
+    }
+    #[test]
+    fn empty_options_test() {
+        let mut dialog = Dialog::new(DialogKind::Attributes, "")
+            .select("owner", "", &[], 0)
+            .radio("kind", "", &[], 0);
+        for code in [KeyCode::Right, KeyCode::End, KeyCode::Char('a')] {
+            dialog.handle_key(key(code));
+        }
+        assert_eq!(dialog.selected("owner"), 0);
+        dialog.handle_key(key(KeyCode::Tab));
+        dialog.handle_key(key(KeyCode::Down));
+        assert_eq!(dialog.selected("kind"), 0);
+        assert!(!dialog.lines(40, &Colors::default()).is_empty());
+    }
+}
@@ -1,6 +1,6 @@
 //! Files list page
 
-use crate::FileType;
+use f_core::{FileSize, FileType};
 
 use super::F;
 
@@ -15,6 +15,7 @@ use ratatui::{
     Frame,
 };
 
+use anyhow::anyhow;
 use std::fs;
 
 pub struct FilesView<'a> {
@@ -24,9 +25,8 @@ pub struct FilesView<'a> {
 fn get_bytes_string(stream: Vec<u8>) -> String {
     let mut s = "".to_string();
     let mut i = 0;
-    let mut cnt = 0;
 
-    for chunk in stream {
+    for (cnt, chunk) in stream.into_iter().enumerate() {
         if cnt == 500 {
             break;
         }
@@ -45,8 +45,6 @@ fn get_bytes_string(stream: Vec<u8>) -> String {
             s.push('\n');
             i = 0;
         }
-
-        cnt += 1;
     }
 
     // Заполняем пропуски. Оставшиеся элементы (нули) - 10-i
@@ -81,7 +79,7 @@ impl<'a> FilesView<'a> {
                 self.f.colors.panels.file_modifier,
             ));
 
-        let view = Paragraph::new(match &self.f.selected {
+        let view = Paragraph::new(match &self.f.panel().selected {
             None => "-- Nothing to show --".to_string(),
             Some(selected) => {
                 if selected.byte_size > 2_u64.pow(20) {
@@ -90,10 +88,10 @@ impl<'a> FilesView<'a> {
                     match selected.file_type {
                         //               text files may be executable
                         FileType::File | FileType::FileExecutable => {
-                            match fs::read(&selected.path) {
+                            match self.f.panel().read(selected, 2_u64.pow(20)) {
                                 Ok(stream) => {
                                     if stream.is_empty() {
-                                        format!("-- Empty file --")
+                                        "-- Empty file --".to_string()
                                     } else {
                                         get_bytes_string(stream)
                                     }
@@ -128,7 +126,7 @@ impl<'a> FilesView<'a> {
                 self.f.colors.panels.file_modifier,
             ));
 
-        let view = Paragraph::new(match &self.f.selected {
+        let view = Paragraph::new(match &self.f.panel().selected {
             None => "-- Nothing to show --".to_string(),
             Some(selected) => {
                 if selected.byte_size > 2_u64.pow(20) {
@@ -137,10 +135,17 @@ impl<'a> FilesView<'a> {
                     match selected.file_type {
                         //               text files may be executable
                         FileType::File | FileType::FileExecutable => {
-                            match fs::read_to_string(&selected.path) {
+                            match self
+                                .f
+                                .panel()
+                                .read(selected, 2_u64.pow(20))
+                                .and_then(|data| {
+                                    String::from_utf8(data)
+                                        .map_err(|_| anyhow!("stream did not contain valid UTF-8"))
+                                }) {
                                 Ok(string) => {
                                     if string.is_empty() {
-                                        format!("-- Empty file --")
+                                        "-- Empty file --".to_string()
                                     } else {
                                         string
                                     }
@@ -158,67 +163,112 @@ impl<'a> FilesView<'a> {
         frame.render_widget(view, area);
     }
 
-    fn files_list(&mut self, area: Rect, frame: &mut Frame) {
+    fn files_list(&mut self, idx: usize, area: Rect, frame: &mut Frame) {
+        let cols = self.f.colors.panels;
+        let show_hidden = self.f.show_hidden;
+        let human_units = self.f.human_units;
+        let is_active = !self.f.two_panels || idx == self.f.tab().active;
+        let panel = &mut self.f.tab_mut().panels[idx];
+
         let mut files_block = Block::bordered()
             .border_set(border::DOUBLE)
-            .style(
-                Style::default().fg(color_from_u8(self.f.colors.panels.file).unwrap_or_default()),
-            )
+            .style(Style::default().fg(color_from_u8(cols.file).unwrap_or_default()))
             .set_style(get_style(
-                self.f.colors.panels.border_active,
-                self.f.colors.panels.file_modifier,
+                if is_active {
+                    cols.border_active
+                } else {
+                    cols.border_inactive
+                },
+                cols.file_modifier,
             ))
-            .title(match &self.f.selected {
+            .title(match &panel.selected {
                 Some(selected) => format!(
                     " {} ({}/{}) ",
                     selected.file_name.to_string_lossy(),
-                    self.f.idx.unwrap_or(0) + 1,
-                    self.f.rows.len()
+                    panel.idx.unwrap_or(0) + 1,
+                    panel.rows.len()
                 ),
                 None => String::new(),
             })
             .title_top(
-                Line::from(format!(" {} files in this dir ", self.f.rows.len())).right_aligned(),
+                Line::from(match &panel.search {
+                    Some(_) => format!(" {} files found ", panel.rows.len() - 1),
+                    None => format!(" {} files in this dir ", panel.rows.len()),
+                })
+                .right_aligned(),
             )
             .title_top(
-                Line::from(format!(
-                    " {} ",
-                    fs::canonicalize(&self.f.current_dir).unwrap().display()
-                ))
+                Line::from({
+                    // Пути внутри архивов не существуют в локальной ФС
+                    let dir =
+                        fs::canonicalize(&panel.current_dir).unwrap_or(panel.current_dir.clone());
+                    match &panel.search {
+                        Some(search) => format!(" Search '{}' in {} ", search.text, dir.display()),
+                        None => format!(" {} ", dir.display()),
+                    }
+                })
                 .centered()
-                .bg(color_from_u8(self.f.colors.panels.header_bg).unwrap_or_default())
-                .fg(color_from_u8(self.f.colors.panels.header_fg).unwrap_or_default()),
+                .bg(color_from_u8(cols.header_bg).unwrap_or_default())
+                .fg(color_from_u8(cols.header_fg).unwrap_or_default()),
             );
 
-        if self.f.show_hidden {
+        if show_hidden {
             files_block = files_block.title_bottom(" Show hidden files ON ");
         }
+        if let Some(filter) = &panel.filter {
+            files_block = files_block.title_bottom(format!(" Filter: {filter} "));
+        }
+        files_block = files_block.title_bottom(
+            Line::from(format!(
+                " Sort: {}{} ",
+                panel.sort,
+                if panel.reverse { " (reversed)" } else { "" }
+            ))
+            .right_aligned(),
+        );
 
-        if self.f.ts.selected().is_none() && !self.f.rows.is_empty() {
-            self.f.ts.select(Some(0));
+        if panel.ts.selected().is_none() && !panel.rows.is_empty() {
+            panel.ts.select(Some(0));
         }
 
-        self.f
+        panel
             .ts
             .selected()
-            .and_then(|n| self.f.rows.get(n).cloned())
-            .clone_into(&mut self.f.selected);
+            .and_then(|n| panel.rows.get(n).cloned())
+            .clone_into(&mut panel.selected);
 
-        let rows = self.f.rows.iter().map(|item| {
+        let rows = panel.rows.iter().map(|item| {
+            let marked = panel.marked.contains(&item.path);
             let style = FileColor {
                 entry: item,
-                cols: self.f.colors.panels,
+                cols,
                 selected: item.is_hidden,
+                marked,
             }
             .style();
 
+            let name = match &item.link_target {
+                Some(target) => format!(
+                    "{} -> {}",
+                    item.file_name.to_string_lossy(),
+                    target.display()
+                ),
+                None => item.file_name.to_string_lossy().to_string(),
+            };
+
+            let size = match human_units {
+                true => item.size(),
+                false => FileSize::Bytes(item.byte_size),
+            }
+            .to_string();
             Row::new(vec![
-                item.file_name
-                    .to_string_lossy()
-                    .to_string()
-                    .set_style(style.clone()),
+                name.set_style(style),
                 item.file_type.to_string().set_style(style),
-                item.size().to_string().into(),
+                if marked {
+                    size.set_style(style)
+                } else {
+                    size.into()
+                },
             ])
         });
         let widths = [
@@ -228,37 +278,47 @@ impl<'a> FilesView<'a> {
         ];
 
         let table = Table::new(rows, widths)
-            .style(
-                Style::new().bg(color_from_u8(self.f.colors.panels.background).unwrap_or_default()),
-            )
+            .style(Style::new().bg(color_from_u8(cols.background).unwrap_or_default()))
             .row_highlight_style(
-                Style::new()
-                    .bg(color_from_u8(self.f.colors.panels.selection_color).unwrap_or_default()),
+                Style::new().bg(color_from_u8(cols.selection_color).unwrap_or_default()),
             )
             .block(files_block.clone());
 
-        StatefulWidget::render(table, area, frame.buffer_mut(), &mut self.f.ts);
+        StatefulWidget::render(table, area, frame.buffer_mut(), &mut panel.ts);
     }
 
     pub fn ui(&mut self, area: Rect, frame: &mut Frame) {
-        if self.f.show_preview {
-            let chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-                .split(area);
-
-            self.files_list(chunks[0], frame);
-            self.panel_preview(chunks[1], frame);
-        } else if self.f.show_bytes {
-            let chunks = Layout::default()
-                .direction(Direction::Horizontal)
-                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
-                .split(area);
-
-            self.files_list(chunks[0], frame);
-            self.panel_bytes(chunks[1], frame);
-        } else {
-            self.files_list(area, frame);
+        let show_side_panel = self.f.show_preview || self.f.show_bytes;
+        if !self.f.two_panels && !show_side_panel {
+            self.files_list(self.f.tab().active, area, frame);
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+            .split(area);
+
+        for (idx, chunk) in chunks.iter().enumerate() {
+            // Предпросмотр файла отображается на месте неактивной панели
+            let is_side_panel = if self.f.two_panels {
+                show_side_panel && idx != self.f.tab().active
+            } else {
+                idx == 1
+            };
+
+            if !is_side_panel {
+                let panel_idx = if self.f.two_panels {
+                    idx
+                } else {
+                    self.f.tab().active
+                };
+                self.files_list(panel_idx, *chunk, frame);
+            } else if self.f.show_preview {
+                self.panel_preview(*chunk, frame);
+            } else {
+                self.panel_bytes(*chunk, frame);
+            }
         }
     }
 }
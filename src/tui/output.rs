@@ -0,0 +1,92 @@
+//! Scrollable window with the output of the command
+
+use super::colors::{color_from_u8, Colors};
+
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{
+    layout::Rect,
+    style::{Style, Stylize},
+    symbols::border,
+    text::Line,
+    widgets::{Block, Clear, Paragraph},
+    Frame,
+};
+
+pub struct Output {
+    pub title: String,
+    pub lines: Vec<String>,
+    /// Index of the first shown line
+    scroll: usize,
+    /// Number of the lines fitting in the window (known after drawing)
+    height: usize,
+}
+
+impl Output {
+    pub fn new(title: String, text: &str) -> Self {
+        Self {
+            title,
+            lines: text
+                .lines()
+                .map(|line| line.replace('\t', "    "))
+                .collect(),
+            scroll: 0,
+            height: 1,
+        }
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    /// Scroll the text. Returns `false` if the window must be closed
+    pub fn handle_key(&mut self, key_event: KeyEvent) -> bool {
+        let page = self.height.max(1);
+        self.scroll = match key_event.code {
+            KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q') => return false,
+            KeyCode::Up | KeyCode::Char('k') => self.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll + 1,
+            KeyCode::PageUp => self.scroll.saturating_sub(page),
+            KeyCode::PageDown | KeyCode::Char(' ') => self.scroll + page,
+            KeyCode::Home => 0,
+            KeyCode::End => self.max_scroll(),
+            _ => self.scroll,
+        }
+        .min(self.max_scroll());
+        true
+    }
+
+    pub fn render(&mut self, area: Rect, frame: &mut Frame, colors: &Colors) {
+        self.height = area.height.saturating_sub(2) as usize;
+        self.scroll = self.scroll.min(self.max_scroll());
+
+        let position = match self.lines.len() {
+            0 => " (no output) ".to_string(),
+            len => format!(
+                " {}-{} of {} lines ",
+                self.scroll + 1,
+                (self.scroll + self.height).min(len),
+                len
+            ),
+        };
+        let block = Block::bordered()
+            .border_set(border::DOUBLE)
+            .title(Line::from(format!(" {} ", self.title)).centered().bold())
+            .title_bottom(Line::from(position).right_aligned())
+            .title_bottom(Line::from(" Esc/q/Enter Close ").left_aligned())
+            .fg(color_from_u8(colors.panels.file).unwrap_or_default())
+            .bg(color_from_u8(colors.panels.background).unwrap_or_default())
+            .border_style(
+                Style::default().fg(color_from_u8(colors.panels.border_active).unwrap_or_default()),
+            );
+        let lines = self
+            .lines
+            .iter()
+            .skip(self.scroll)
+            .take(self.height)
+            .map(|line| Line::from(line.as_str()))
+            .collect::<Vec<_>>();
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
@@ -0,0 +1,356 @@
+//! Files panel: listed directory, cursor position and marked files
+
+use crate::find::{find, Query};
+use crate::session::PanelState;
+use crate::vfs::{self, Vfs};
+use f_core::history::History;
+use f_core::utils::{get_home, glob_match, sort_entries, SortBy};
+use f_core::{FileEntry, FileType};
+
+use anyhow::Result;
+use ratatui::widgets::TableState;
+
+use std::{
+    collections::HashSet,
+    ffi::OsString,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Results of the files search shown instead of the directory contents
+pub struct Search {
+    /// Query text entered by user
+    pub text: String,
+    pub query: Query,
+}
+
+pub struct Panel {
+    pub ts: TableState,
+    pub current_dir: PathBuf,
+    pub search: Option<Search>,
+    /// File system of the current directory
+    pub vfs: Box<dyn Vfs>,
+    pub rows: Vec<FileEntry>,
+    pub marked: HashSet<PathBuf>,
+    pub selected: Option<FileEntry>,
+    pub idx: Option<usize>,
+
+    pub sort: SortBy,
+    /// Reverse sort order
+    pub reverse: bool,
+    /// Show only files which names match this pattern
+    pub filter: Option<String>,
+    pub history: History,
+}
+
+impl Panel {
+    pub fn new<P: AsRef<Path>>(pth: P, show_hidden: bool) -> Result<Self> {
+        let vfs = vfs::open(pth.as_ref())?;
+        let rows = vfs.list(pth.as_ref(), show_hidden)?;
+
+        Ok(Self {
+            current_dir: pth.as_ref().to_path_buf(),
+            ts: TableState::default(),
+            search: None,
+            vfs,
+            selected: rows.first().cloned(),
+            rows,
+            marked: HashSet::new(),
+            idx: None,
+            sort: SortBy::default(),
+            reverse: false,
+            filter: None,
+            history: History::default(),
+        })
+    }
+
+    /// Restore the panel saved in the session file
+    pub fn from_state(state: &PanelState, show_hidden: bool) -> Result<Self> {
+        let mut panel = Self::new(&state.dir, show_hidden)?;
+        panel.sort = state.sort;
+        panel.reverse = state.reverse;
+        panel.filter = state.filter.clone();
+        panel.history = state.history.clone();
+        panel.rescan_dir(show_hidden)?;
+        if let Some(name) = &state.selected {
+            panel.select_name(name);
+        }
+
+        Ok(panel)
+    }
+
+    pub fn state(&self) -> PanelState {
+        PanelState {
+            // Подключение к удалённым хостам при запуске не восстанавливается
+            dir: match vfs::is_url(&self.current_dir) {
+                true => get_home(),
+                false => fs::canonicalize(&self.current_dir).unwrap_or(self.current_dir.clone()),
+            },
+            selected: self
+                .selected
+                .as_ref()
+                .map(|entry| entry.file_name.to_string_lossy().to_string()),
+            sort: self.sort,
+            reverse: self.reverse,
+            filter: self.filter.clone(),
+            history: self.history.clone(),
+        }
+    }
+
+    pub fn rescan_dir(&mut self, show_hidden: bool) -> Result<()> {
+        self.rows = match &self.search {
+            Some(search) => {
+                let mut rows = find(&self.current_dir, &search.query, show_hidden)?;
+                // Переход "наверх" из результатов поиска возвращает в директорию,
+                // в которой производился поиск
+                rows.insert(
+                    0,
+                    FileEntry {
+                        file_name: OsString::from_str(".. [UP]").unwrap(),
+                        path: self.current_dir.clone(),
+                        byte_size: 4096,
+                        file_type: FileType::ParentDirectory,
+                        is_hidden: false,
+                        link_target: None,
+                        mtime: None,
+                    },
+                );
+                rows
+            }
+            None => self.vfs.list(&self.current_dir, show_hidden)?,
+        };
+        if let Some(filter) = &self.filter {
+            // Директории (и ссылки на них) не скрываются, чтобы можно было
+            // перемещаться по ФС
+            let vfs = &self.vfs;
+            self.rows.retain(|entry| match entry.file_type {
+                FileType::Directory | FileType::ParentDirectory => true,
+                FileType::Link if vfs.stat(&entry.path).is_ok_and(|stat| stat.is_dir()) => true,
+                _ => glob_match(filter, &entry.file_name.to_string_lossy()),
+            });
+        }
+        sort_entries(&mut self.rows, self.sort, self.reverse);
+        // Отметки файлов из других директорий (или уже удалённых файлов)
+        // больше не нужны
+        let rows = &self.rows;
+        self.marked
+            .retain(|pth| rows.iter().any(|entry| &entry.path == pth));
+
+        // Курсор остаётся на том же файле, а если файла больше нет - на той
+        // же строке
+        let idx = self
+            .selected
+            .as_ref()
+            .and_then(|selected| rows.iter().position(|entry| entry.path == selected.path))
+            .unwrap_or(self.idx.unwrap_or(0).min(rows.len().saturating_sub(1)));
+        self.select_idx(idx);
+        Ok(())
+    }
+
+    /// Move cursor to the row with the index
+    fn select_idx(&mut self, idx: usize) {
+        if !self.rows.is_empty() {
+            self.idx = Some(idx);
+            self.ts.select(self.idx);
+        }
+        self.selected = self.rows.get(idx).cloned();
+    }
+
+    /// Go to the another directory and remember the old one in the history
+    pub fn cd<P: AsRef<Path>>(&mut self, pth: P, show_hidden: bool) -> Result<()> {
+        let old_cur_dir = self.current_dir.clone();
+        self.change_dir(pth, show_hidden)?;
+        if self.current_dir != old_cur_dir {
+            self.history.push(old_cur_dir);
+        }
+        Ok(())
+    }
+
+    /// Go to the previous directory from the history
+    pub fn go_back(&mut self, show_hidden: bool) -> Result<()> {
+        let mut history = self.history.clone();
+        if let Some(pth) = history.back(&self.current_dir) {
+            self.change_dir(pth, show_hidden)?;
+            self.history = history;
+        }
+        Ok(())
+    }
+
+    /// Go to the next directory from the history
+    pub fn go_forward(&mut self, show_hidden: bool) -> Result<()> {
+        let mut history = self.history.clone();
+        if let Some(pth) = history.forward(&self.current_dir) {
+            self.change_dir(pth, show_hidden)?;
+            self.history = history;
+        }
+        Ok(())
+    }
+
+    /// Go to the another directory. The old directory is restored on error
+    fn change_dir<P: AsRef<Path>>(&mut self, pth: P, show_hidden: bool) -> Result<()> {
+        // Переход в архив или на удалённый хост открывает другую ФС
+        let (pth, old_vfs) = match self.vfs.contains(pth.as_ref()) {
+            true => (pth.as_ref().to_path_buf(), None),
+            false => {
+                let vfs = vfs::open(pth.as_ref())?;
+                let pth = vfs.canonicalize(pth.as_ref())?;
+                (pth, Some(std::mem::replace(&mut self.vfs, vfs)))
+            }
+        };
+        let old_cur_dir = std::mem::replace(&mut self.current_dir, pth);
+        let old_search = self.search.take();
+
+        if let Err(why) = self.rescan_dir(show_hidden) {
+            self.current_dir = old_cur_dir;
+            self.search = old_search;
+            if let Some(vfs) = old_vfs {
+                self.vfs = vfs;
+            }
+            return Err(why);
+        }
+        self.select_idx(0);
+        Ok(())
+    }
+
+    /// Enter the directory (or the archive). The cursor is moved to the
+    /// directory we came from after going up
+    pub fn open(&mut self, entry: &FileEntry, show_hidden: bool) -> Result<()> {
+        let old_cur_dir = self.current_dir.clone();
+        self.cd(&entry.path, show_hidden)?;
+        if old_cur_dir.parent() == Some(self.current_dir.as_path()) {
+            if let Some(name) = old_cur_dir.file_name() {
+                self.select_name(&name.to_string_lossy());
+            }
+        }
+        Ok(())
+    }
+
+    /// Read contents of the file (not more than `limit` bytes)
+    pub fn read(&self, entry: &FileEntry, limit: u64) -> Result<Vec<u8>> {
+        let mut data = Vec::new();
+        self.vfs
+            .open_read(&entry.path)?
+            .take(limit)
+            .read_to_end(&mut data)?;
+        Ok(data)
+    }
+
+    /// Show files matching the query instead of the directory contents
+    pub fn search(&mut self, text: String, show_hidden: bool) -> Result<()> {
+        vfs::check_local(self.vfs.as_ref(), "Search")?;
+        let query = text.parse::<Query>()?;
+
+        let old_search = self.search.replace(Search { text, query });
+        if let Err(why) = self.rescan_dir(show_hidden) {
+            self.search = old_search;
+            return Err(why);
+        }
+        self.select_idx(0);
+        Ok(())
+    }
+
+    /// Move cursor to the file with the specified name
+    pub fn select_name(&mut self, name: &str) {
+        if let Some(idx) = self
+            .rows
+            .iter()
+            .position(|entry| entry.file_name.to_string_lossy() == name)
+        {
+            self.ts.select(Some(idx));
+            self.update_idx();
+        }
+    }
+
+    pub fn update_idx(&mut self) {
+        self.rows
+            .get(self.ts.selected().unwrap_or(0))
+            .cloned()
+            .clone_into(&mut self.selected);
+        self.idx = Some(self.ts.selected().unwrap_or(0));
+    }
+
+    pub fn select_next(&mut self) {
+        if self.idx.unwrap_or(0) + 1 < self.rows.len() {
+            self.ts.select_next();
+            self.update_idx();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.ts.select_previous();
+        self.update_idx();
+    }
+
+    pub fn select_first(&mut self) {
+        if !self.rows.is_empty() {
+            self.ts.select(Some(0));
+            self.update_idx();
+        }
+    }
+
+    pub fn select_last(&mut self) {
+        if !self.rows.is_empty() {
+            self.ts.select(Some(self.rows.len() - 1));
+            self.update_idx();
+        }
+    }
+
+    /// Get files for the file operations: marked files or (if nothing is
+    /// marked) the selected one
+    pub fn targets(&self) -> Vec<FileEntry> {
+        if self.marked.is_empty() {
+            self.selected
+                .iter()
+                .filter(|entry| !entry.is_parent())
+                .cloned()
+                .collect()
+        } else {
+            self.rows
+                .iter()
+                .filter(|entry| self.marked.contains(&entry.path))
+                .cloned()
+                .collect()
+        }
+    }
+
+    /// Total size of the marked files
+    pub fn marked_size(&self) -> u64 {
+        self.rows
+            .iter()
+            .filter(|entry| self.marked.contains(&entry.path))
+            .map(|entry| entry.byte_size)
+            .sum()
+    }
+
+    pub fn toggle_mark(&mut self) {
+        if let Some(selected) = &self.selected {
+            if !selected.is_parent() && !self.marked.remove(&selected.path) {
+                self.marked.insert(selected.path.clone());
+            }
+        }
+        self.select_next();
+    }
+
+    /// Mark (or unmark) files which names match the pattern
+    pub fn mark_by_pattern(&mut self, pattern: &str, mark: bool) {
+        for entry in self.rows.iter().filter(|entry| !entry.is_parent()) {
+            if glob_match(pattern, &entry.file_name.to_string_lossy()) {
+                if mark {
+                    self.marked.insert(entry.path.clone());
+                } else {
+                    self.marked.remove(&entry.path);
+                }
+            }
+        }
+    }
+
+    pub fn invert_marks(&mut self) {
+        for entry in self.rows.iter().filter(|entry| !entry.is_parent()) {
+            if !self.marked.remove(&entry.path) {
+                self.marked.insert(entry.path.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,59 @@
+//! Tab with its own pair of panels
+
+use super::panel::Panel;
+use crate::session::TabState;
+
+use anyhow::{anyhow, Result};
+use std::{fs, path::Path};
+
+pub struct Tab {
+    pub panels: [Panel; 2],
+
+    /// Index of the active panel in `panels`
+    pub active: usize,
+}
+
+impl Tab {
+    pub fn new<P: AsRef<Path>>(pth: P, show_hidden: bool) -> Result<Self> {
+        Ok(Self {
+            panels: [
+                Panel::new(&pth, show_hidden)?,
+                Panel::new(&pth, show_hidden)?,
+            ],
+            active: 0,
+        })
+    }
+
+    /// Restore the tab saved in the session file
+    pub fn from_state(state: &TabState, show_hidden: bool) -> Result<Self> {
+        let [left, right] = &state.panels[..] else {
+            return Err(anyhow!("Tab must contain exactly 2 panels"));
+        };
+
+        Ok(Self {
+            panels: [
+                Panel::from_state(left, show_hidden)?,
+                Panel::from_state(right, show_hidden)?,
+            ],
+            active: state.active_panel.min(1),
+        })
+    }
+
+    pub fn state(&self) -> TabState {
+        TabState {
+            active_panel: self.active,
+            panels: self.panels.iter().map(|panel| panel.state()).collect(),
+        }
+    }
+
+    /// Name of the directory in the active panel (for the tab bar)
+    pub fn title(&self) -> String {
+        let dir = &self.panels[self.active].current_dir;
+        let dir = fs::canonicalize(dir).unwrap_or(dir.to_path_buf());
+
+        match dir.file_name() {
+            Some(name) => name.to_string_lossy().to_string(),
+            None => dir.display().to_string(),
+        }
+    }
+}
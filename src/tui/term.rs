@@ -0,0 +1,59 @@
+//! Initialization and restoring of the terminal
+//!
+//! Unlike `ratatui::init()` the interface is drawn on `/dev/tty` if stdout is
+//! redirected, so the chosen files may be printed to stdout (e.g.
+//! `f --choose-files - | xargs ...`)
+
+use anyhow::Result;
+use crossterm::{
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ratatui::backend::CrosstermBackend;
+use std::{
+    fs::File,
+    io::{self, IsTerminal, Write},
+    panic,
+    sync::Once,
+};
+
+pub type Terminal = ratatui::Terminal<CrosstermBackend<Box<dyn Write>>>;
+
+/// Terminal device opened for writing if stdout is redirected
+pub fn redirected_tty() -> Option<File> {
+    if io::stdout().is_terminal() {
+        return None;
+    }
+    File::options().write(true).open("/dev/tty").ok()
+}
+
+/// Output of the interface. Everything shown to the user (e.g. escape
+/// sequences for the terminal) must be written here, not to stdout
+pub fn tty() -> Box<dyn Write> {
+    match redirected_tty() {
+        Some(tty) => Box::new(tty),
+        None => Box::new(io::stdout()),
+    }
+}
+
+pub fn init() -> Result<Terminal> {
+    static PANIC_HOOK: Once = Once::new();
+    PANIC_HOOK.call_once(|| {
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            restore();
+            hook(info);
+        }));
+    });
+
+    terminal::enable_raw_mode()?;
+    let mut out = tty();
+    execute!(out, EnterAlternateScreen)?;
+    Ok(Terminal::new(CrosstermBackend::new(out))?)
+}
+
+pub fn restore() {
+    // Ошибки игнорируются: терминал восстанавливается и при панике
+    let _ = terminal::disable_raw_mode();
+    let _ = execute!(tty(), LeaveAlternateScreen);
+}
@@ -2,8 +2,8 @@
 
 use crate::consts::{PROG_NAME, PROG_VER};
 use crate::tui::colors::{color_from_u8, get_style, Colors, FileColor};
-use crate::utils::get_home;
-use crate::{traits::Toml, utils::read_dir, FileEntry, FileType};
+use f_core::utils::get_home;
+use f_core::{traits::Toml, utils::read_dir, FileEntry, FileType};
 
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind};
@@ -47,7 +47,7 @@ impl F {
             current_dir: pth.as_ref().to_path_buf(),
             colors: Colors::parse("./colors.toml").unwrap_or_default(),
             ts: TableState::default(),
-            selected: if rows.len() > 0 {
+            selected: if !rows.is_empty() {
                 Some(rows[0].clone())
             } else {
                 None
@@ -64,7 +64,7 @@ impl F {
 
     fn rescan_dir(&mut self) -> Result<()> {
         self.rows = read_dir(&self.current_dir, self.show_hidden)?;
-        if self.rows.len() > 0 {
+        if !self.rows.is_empty() {
             self.idx = Some(0);
             self.ts.select(self.idx);
         }
@@ -137,27 +137,21 @@ impl F {
                 self.exit();
             }
 
-            KeyCode::Down | KeyCode::Char('j') => {
-                if self.idx.unwrap_or(0) < (self.rows.len() - 1) {
-                    self.ts.select_next();
-                    self.update_idx();
-                }
+            KeyCode::Down | KeyCode::Char('j') if self.idx.unwrap_or(0) < (self.rows.len() - 1) => {
+                self.ts.select_next();
+                self.update_idx();
             }
             KeyCode::Up | KeyCode::Char('k') => {
                 self.ts.select_previous();
                 self.update_idx();
             }
-            KeyCode::Home | KeyCode::Char('H') => {
-                if self.rows.len() > 0 {
-                    self.idx = Some(0);
-                    self.ts.select(self.idx);
-                }
+            KeyCode::Home | KeyCode::Char('H') if !self.rows.is_empty() => {
+                self.idx = Some(0);
+                self.ts.select(self.idx);
             }
-            KeyCode::End | KeyCode::Char('L') => {
-                if self.rows.len() > 0 {
-                    self.idx = Some(self.rows.len() - 1);
-                    self.ts.select(self.idx);
-                }
+            KeyCode::End | KeyCode::Char('L') if !self.rows.is_empty() => {
+                self.idx = Some(self.rows.len() - 1);
+                self.ts.select(self.idx);
             }
             KeyCode::Char('~') => {
                 let pth = get_home();
@@ -167,6 +161,8 @@ impl F {
                     byte_size: 4096,
                     file_type: FileType::Directory,
                     is_hidden: false,
+                    link_target: None,
+                    mtime: None,
                 });
                 self.current_dir = pth;
 
@@ -182,6 +178,8 @@ impl F {
                     byte_size: 4096,
                     file_type: FileType::Directory,
                     is_hidden: false,
+                    link_target: None,
+                    mtime: None,
                 });
                 self.current_dir = pth;
 
@@ -323,7 +321,7 @@ impl<'a> FilesView<'a> {
                             match fs::read_to_string(&selected.path) {
                                 Ok(string) => {
                                     if string.is_empty() {
-                                        format!("-- Empty file --")
+                                        "-- Empty file --".to_string()
                                     } else {
                                         string
                                     }
@@ -402,6 +400,7 @@ impl<'a> FilesView<'a> {
                 entry: item,
                 cols: self.f.colors.panels,
                 selected: item.is_hidden,
+                marked: false,
             }
             .style();
 
@@ -409,7 +408,7 @@ impl<'a> FilesView<'a> {
                 item.file_name
                     .to_string_lossy()
                     .to_string()
-                    .set_style(style.clone()),
+                    .set_style(style),
                 item.file_type.to_string().set_style(style),
                 item.size().to_string().into(),
             ])
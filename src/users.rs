@@ -0,0 +1,67 @@
+//! Users and groups from `/etc/passwd` and `/etc/group`
+
+use std::fs;
+
+use crate::consts::{GROUP_FILE, PASSWD_FILE};
+
+/// User or group account
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub name: String,
+    pub id: u32,
+}
+
+/// Parse `name:x:id:...` lines of the `passwd(5)` and `group(5)` files
+fn parse_accounts(contents: &str) -> Vec<Account> {
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let fields = line.split(':').collect::<Vec<_>>();
+            Some(Account {
+                name: fields.first()?.to_string(),
+                id: fields.get(2)?.parse().ok()?,
+            })
+        })
+        .collect()
+}
+
+fn read_accounts(pth: &str) -> Vec<Account> {
+    fs::read_to_string(pth)
+        .map(|contents| parse_accounts(&contents))
+        .unwrap_or_default()
+}
+
+/// Get all users from `/etc/passwd`
+pub fn users() -> Vec<Account> {
+    read_accounts(PASSWD_FILE)
+}
+
+/// Get all groups from `/etc/group`
+pub fn groups() -> Vec<Account> {
+    read_accounts(GROUP_FILE)
+}
+
+/// Get UID by user name or number
+pub fn uid(name: &str) -> Option<u32> {
+    name.parse()
+        .ok()
+        .or_else(|| users().into_iter().find(|u| u.name == name).map(|u| u.id))
+}
+
+/// Get GID by group name or number
+pub fn gid(name: &str) -> Option<u32> {
+    name.parse()
+        .ok()
+        .or_else(|| groups().into_iter().find(|g| g.name == name).map(|g| g.id))
+}
+
+/// Get user name by UID
+pub fn user_name(uid: u32) -> Option<String> {
+    users().into_iter().find(|u| u.id == uid).map(|u| u.name)
+}
+
+/// Get group name by GID
+pub fn group_name(gid: u32) -> Option<String> {
+    groups().into_iter().find(|g| g.id == gid).map(|g| g.name)
+}
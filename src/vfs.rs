@@ -0,0 +1,310 @@
+//! Virtual file system: the same interface for the local files and other
+//! sources of the files shown in the panels (e.g. archives)
+
+mod archive;
+mod local;
+pub mod sftp;
+
+pub use archive::ArchiveFs;
+pub use local::LocalFs;
+pub use sftp::SftpFs;
+
+use crate::archive::Format;
+use f_core::{FileEntry, FileType};
+
+use anyhow::{anyhow, Result};
+use std::{
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// Information about the file
+#[derive(Debug, Clone)]
+pub struct Stat {
+    pub file_type: FileType,
+    /// Size in bytes
+    pub size: u64,
+    /// Permission bits
+    pub mode: u32,
+    pub mtime: Option<SystemTime>,
+
+    /// Path the symbolic link points to
+    pub link_target: Option<PathBuf>,
+}
+
+impl Stat {
+    pub fn is_dir(&self) -> bool {
+        matches!(self.file_type, FileType::Directory)
+    }
+
+    /// Make the entry of the listing
+    pub fn entry(&self, pth: PathBuf) -> FileEntry {
+        let file_name = pth.file_name().unwrap_or_default().to_os_string();
+        FileEntry {
+            is_hidden: file_name.to_string_lossy().starts_with('.'),
+            file_name,
+            path: pth,
+            byte_size: self.size,
+            file_type: self.file_type.clone(),
+            link_target: self.link_target.clone(),
+            mtime: self.mtime,
+        }
+    }
+}
+
+/// File system shown in the panel. Paths of all files are absolute
+pub trait Vfs {
+    /// Check if the path belongs to this file system
+    fn contains(&self, pth: &Path) -> bool;
+
+    /// Text shown in the footer (`None` for the local files)
+    fn description(&self) -> Option<String> {
+        None
+    }
+
+    /// Files are on the local file system (and may be used by the programs)
+    fn is_local(&self) -> bool {
+        false
+    }
+
+    /// Files can only be read (e.g. in archives)
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// List the directory. `.. [UP]` entry is the first (if the directory
+    /// isn't the root)
+    fn list(&self, dir: &Path, show_hidden: bool) -> Result<Vec<FileEntry>>;
+
+    /// Get the absolute path without links and `..`
+    fn canonicalize(&self, pth: &Path) -> Result<PathBuf>;
+
+    /// Get information about the file (symbolic links are followed if
+    /// possible)
+    fn stat(&self, pth: &Path) -> Result<Stat>;
+
+    fn open_read(&self, pth: &Path) -> Result<Box<dyn Read>>;
+
+    /// Create the file with `mode` permissions or truncate the existing one
+    fn open_write(&self, pth: &Path, mode: u32) -> Result<Box<dyn Write>>;
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()>;
+
+    /// Rename the file only if `to` doesn't exist (the check and renaming
+    /// are atomic)
+    fn rename_noreplace(&self, from: &Path, to: &Path) -> Result<()>;
+
+    /// Remove file or directory with all its contents
+    fn remove(&self, pth: &Path) -> Result<()>;
+
+    /// Create directory with all its parents. The last directory gets the
+    /// `mode` permissions
+    fn mkdir(&self, pth: &Path, mode: u32) -> Result<()>;
+
+    /// Create symbolic link `pth` pointing to `target`
+    fn symlink(&self, target: &Path, pth: &Path) -> Result<()>;
+}
+
+/// Check that the files are on the local file system. Operations which
+/// aren't a part of `Vfs` (e.g. search, links, permissions or the recycle
+/// bin) work only with the local files
+pub fn check_local(fs: &dyn Vfs, operation: &str) -> Result<()> {
+    if fs.is_local() {
+        return Ok(());
+    }
+    Err(anyhow!(
+        "{operation} is not supported on this file system ({})",
+        fs.description().unwrap_or_default()
+    ))
+}
+
+/// Find the archive which contains the path (or is the path itself)
+pub fn archive_file(pth: &Path) -> Option<&Path> {
+    pth.ancestors()
+        .find(|file| Format::from_path(file).is_some() && file.is_file())
+}
+
+/// Check if the path is the address of the remote file (`sftp://...`)
+pub fn is_url(pth: &Path) -> bool {
+    sftp::root(pth).is_some()
+}
+
+/// Join the path to the `base` directory (addresses of the remote files are
+/// absolute)
+pub fn join(base: &Path, pth: &Path) -> PathBuf {
+    match is_url(pth) {
+        true => pth.to_path_buf(),
+        false => base.join(pth),
+    }
+}
+
+/// Open the file system containing the path
+pub fn open(pth: &Path) -> Result<Box<dyn Vfs>> {
+    if let Some(root) = sftp::root(pth) {
+        return Ok(Box::new(SftpFs::connect(&root)?));
+    }
+    Ok(match archive_file(pth) {
+        Some(file) => Box::new(ArchiveFs::open(file)?),
+        None => Box::new(LocalFs),
+    })
+}
+
+/// Copy the file or directory with all its contents to another (or the same)
+/// file system. The destination must not exist. Symbolic links are copied as
+/// links (like `f_core::utils::copy_recursive()` does)
+pub fn copy(from_fs: &dyn Vfs, from: &Path, to_fs: &dyn Vfs, to: &Path) -> Result<()> {
+    if to_fs.stat(to).is_ok() {
+        return Err(anyhow!("File '{}' already exists", to.display()));
+    }
+
+    let stat = from_fs.stat(from)?;
+    // Ссылка на один из родительских каталогов зациклила бы копирование
+    if let Some(target) = &stat.link_target {
+        return to_fs.symlink(target, to);
+    }
+    if stat.is_dir() {
+        to_fs.mkdir(to, stat.mode)?;
+        for entry in from_fs.list(from, true)? {
+            if !entry.is_parent() {
+                copy(from_fs, &entry.path, to_fs, &to.join(&entry.file_name))?;
+            }
+        }
+        return Ok(());
+    }
+
+    let mut reader = from_fs.open_read(from)?;
+    let mut writer = to_fs.open_write(to, stat.mode)?;
+    // Буферизованные данные записываются при `flush()`, а не при удалении
+    // `writer`, иначе ошибки записи будут потеряны
+    io::copy(&mut reader, &mut writer)
+        .and_then(|_| writer.flush())
+        .map_err(|why| {
+            anyhow!(
+                "Failed to copy '{}' to '{}': {}",
+                from.display(),
+                to.display(),
+                why
+            )
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::archive::Archive;
+    use f_core::test_utils::TempDir;
+    use std::fs;
+
+    #[test]
+    fn vfs_test() {
+        let dir = TempDir::new("vfs");
+        let local = open(&dir).unwrap();
+        assert!(!local.is_read_only());
+
+        local.mkdir(&dir.join("src/sub"), 0o755).unwrap();
+        local
+            .open_write(&dir.join("src/a"), 0o644)
+            .unwrap()
+            .write_all(b"abc")
+            .unwrap();
+        local
+            .rename(&dir.join("src/a"), &dir.join("src/b"))
+            .unwrap();
+        assert_eq!(local.stat(&dir.join("src/b")).unwrap().size, 3);
+        assert!(local.stat(&dir.join("src/sub")).unwrap().is_dir());
+        let names = local
+            .list(&dir.join("src"), false)
+            .unwrap()
+            .iter()
+            .map(|entry| entry.file_name.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, [".. [UP]", "b", "sub"]);
+
+        let file = dir.join("src.zip");
+        let archive = Archive {
+            format: Format::Zip,
+            level: 6,
+            exclude_hidden: false,
+        };
+        archive
+            .create(&[dir.join("src")], &file, &mut |_| true)
+            .unwrap();
+        let zip = open(&file.join("src")).unwrap();
+        assert!(zip.is_read_only() && zip.contains(&file.join("src/b")));
+        assert!(!local.contains(&file.join("src/b")));
+
+        let entries = zip.list(&file.join("src"), false).unwrap();
+        assert_eq!(entries[0].path, file);
+        assert_eq!(entries[1].path, file.join("src/b"));
+        let mut data = String::new();
+        zip.open_read(&file.join("src/b"))
+            .unwrap()
+            .read_to_string(&mut data)
+            .unwrap();
+        assert_eq!(data, "abc");
+        assert!(zip.stat(&file.join("src/sub")).unwrap().is_dir());
+        assert!(zip.list(&file.join("src/b"), false).is_err());
+        assert!(zip.remove(&file.join("src/b")).is_err());
+        assert!(check_local(local.as_ref(), "Search").is_ok());
+        assert!(check_local(zip.as_ref(), "Search")
+            .unwrap_err()
+            .to_string()
+            .starts_with("Search is not supported"));
+
+        // Распаковка через копирование между ФС
+        copy(
+            zip.as_ref(),
+            &file.join("src"),
+            local.as_ref(),
+            &dir.join("copy"),
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(dir.join("copy/b")).unwrap(), "abc");
+        assert!(local.stat(&dir.join("copy/sub")).unwrap().is_dir());
+        assert!(copy(
+            zip.as_ref(),
+            &file.join("src"),
+            local.as_ref(),
+            &dir.join("copy")
+        )
+        .is_err());
+
+        local.remove(&dir.join("src")).unwrap();
+        assert!(local.stat(&dir.join("src")).is_err());
+    }
+    #[test]
+    fn copy_link_test() {
+        let dir = TempDir::new("copy_link");
+        fs::create_dir_all(dir.join("src/sub")).unwrap();
+        fs::write(dir.join("src/a"), "abc").unwrap();
+        std::os::unix::fs::symlink("..", dir.join("src/sub/up")).unwrap();
+        std::os::unix::fs::symlink(dir.join("src"), dir.join("src/abs")).unwrap();
+
+        // Ссылки на родительские каталоги копируются как ссылки, а не
+        // обходятся бесконечно
+        copy(&LocalFs, &dir.join("src"), &LocalFs, &dir.join("copy")).unwrap();
+        assert_eq!(fs::read_to_string(dir.join("copy/a")).unwrap(), "abc");
+        assert_eq!(
+            fs::read_link(dir.join("copy/sub/up")).unwrap(),
+            Path::new("..")
+        );
+        assert_eq!(
+            fs::read_link(dir.join("copy/abs")).unwrap(),
+            dir.join("src")
+        );
+
+        // Скопированная ссылка может указывать в сам каталог назначения
+        copy(
+            &LocalFs,
+            &dir.join("src/abs"),
+            &LocalFs,
+            &dir.join("src/sub/abs"),
+        )
+        .unwrap();
+        assert!(fs::symlink_metadata(dir.join("src/sub/abs"))
+            .unwrap()
+            .is_symlink());
+    }
+}
@@ -0,0 +1,179 @@
+//! Files of the archive (read-only). Path of the member is the path of the
+//! archive file with the path of the member in the archive appended (e.g.
+//! `/tmp/a.tar.gz/dir/file`)
+
+use super::{Stat, Vfs};
+use crate::archive::{self, Member, MemberKind};
+use f_core::{FileEntry, FileType};
+
+use anyhow::{anyhow, Result};
+use std::{
+    cell::RefCell,
+    ffi::OsString,
+    io::{Cursor, Read, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+pub struct ArchiveFs {
+    /// Path to the archive file (root of the file system)
+    file: PathBuf,
+    members: Vec<Member>,
+
+    /// Contents of the last read member: reading from the compressed tar is
+    /// slow, and preview is redrawn after every key press
+    cache: RefCell<Option<(PathBuf, Vec<u8>)>>,
+}
+
+impl ArchiveFs {
+    pub fn open<P: AsRef<Path>>(file: P) -> Result<Self> {
+        Ok(Self {
+            file: file.as_ref().to_path_buf(),
+            members: archive::list(&file)?,
+            cache: RefCell::new(None),
+        })
+    }
+
+    /// Get path of the member in the archive by its path in the file system
+    fn member_name<'a>(&self, pth: &'a Path) -> Result<&'a Path> {
+        pth.strip_prefix(&self.file)
+            .map_err(|_| anyhow!("'{}' is not in the archive", pth.display()))
+    }
+
+    fn member(&self, pth: &Path) -> Result<&Member> {
+        let name = self.member_name(pth)?;
+        self.members
+            .iter()
+            .find(|member| member.name == name)
+            .ok_or_else(|| anyhow!("'{}' not found in the archive", pth.display()))
+    }
+
+    fn read_only(&self) -> anyhow::Error {
+        anyhow!(
+            "Archive '{}' is read-only (extract files to change them)",
+            self.file.display()
+        )
+    }
+}
+
+impl From<&Member> for Stat {
+    fn from(member: &Member) -> Self {
+        Self {
+            file_type: match &member.kind {
+                MemberKind::File if member.mode & 0o111 != 0 => FileType::FileExecutable,
+                MemberKind::File => FileType::File,
+                MemberKind::Dir => FileType::Directory,
+                MemberKind::Symlink(_) => FileType::Link,
+                MemberKind::Other => FileType::Special,
+            },
+            size: member.size,
+            mode: member.mode,
+            mtime: member.mtime,
+            link_target: match &member.kind {
+                MemberKind::Symlink(target) => Some(target.clone()),
+                _ => None,
+            },
+        }
+    }
+}
+
+impl Vfs for ArchiveFs {
+    fn contains(&self, pth: &Path) -> bool {
+        pth.starts_with(&self.file)
+    }
+
+    fn description(&self) -> Option<String> {
+        Some(format!(
+            "Archive: {} (read-only)",
+            self.file.file_name().unwrap_or_default().to_string_lossy()
+        ))
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn list(&self, dir: &Path, show_hidden: bool) -> Result<Vec<FileEntry>> {
+        if dir != self.file && !self.stat(dir)?.is_dir() {
+            return Err(anyhow!("'{}' is not a directory", dir.display()));
+        }
+        let name = self.member_name(dir)?;
+
+        // Выход "наверх" из корня архива ведёт в директорию с архивом
+        let mut rows = vec![FileEntry {
+            file_name: OsString::from_str(".. [UP]").unwrap(),
+            path: dir.parent().unwrap_or(Path::new("/")).to_path_buf(),
+            byte_size: 4096,
+            file_type: FileType::ParentDirectory,
+            is_hidden: false,
+            link_target: None,
+            mtime: None,
+        }];
+        rows.extend(
+            self.members
+                .iter()
+                .filter(|member| member.name.parent() == Some(name))
+                .map(|member| Stat::from(member).entry(self.file.join(&member.name)))
+                .filter(|entry| show_hidden || !entry.is_hidden),
+        );
+        Ok(rows)
+    }
+
+    fn canonicalize(&self, pth: &Path) -> Result<PathBuf> {
+        Ok(pth.to_path_buf())
+    }
+
+    fn stat(&self, pth: &Path) -> Result<Stat> {
+        if pth == self.file {
+            return Ok(Stat {
+                file_type: FileType::Directory,
+                size: 0,
+                mode: 0o755,
+                mtime: None,
+                link_target: None,
+            });
+        }
+        self.member(pth).map(Stat::from)
+    }
+
+    /// Member is read into memory
+    fn open_read(&self, pth: &Path) -> Result<Box<dyn Read>> {
+        let member = self.member(pth)?;
+        if member.kind != MemberKind::File {
+            return Err(anyhow!("'{}' is not a file", pth.display()));
+        }
+        if let Some((cached, data)) = &*self.cache.borrow() {
+            if cached == pth {
+                return Ok(Box::new(Cursor::new(data.clone())));
+            }
+        }
+
+        let data = archive::read_member(&self.file, &member.name, u64::MAX)?;
+        *self.cache.borrow_mut() = Some((pth.to_path_buf(), data.clone()));
+        Ok(Box::new(Cursor::new(data)))
+    }
+
+    fn open_write(&self, _pth: &Path, _mode: u32) -> Result<Box<dyn Write>> {
+        Err(self.read_only())
+    }
+
+    fn rename(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn rename_noreplace(&self, _from: &Path, _to: &Path) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn remove(&self, _pth: &Path) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn mkdir(&self, _pth: &Path, _mode: u32) -> Result<()> {
+        Err(self.read_only())
+    }
+
+    fn symlink(&self, _target: &Path, _pth: &Path) -> Result<()> {
+        Err(self.read_only())
+    }
+}
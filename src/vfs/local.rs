@@ -0,0 +1,146 @@
+//! Files of the local file system
+
+use super::{archive_file, is_url, Stat, Vfs};
+use crate::create::create_dir;
+use f_core::utils::{read_dir, remove_path};
+use f_core::FileType;
+
+use anyhow::{anyhow, Result};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    path::{Path, PathBuf},
+};
+
+pub struct LocalFs;
+
+impl Vfs for LocalFs {
+    fn contains(&self, pth: &Path) -> bool {
+        archive_file(pth).is_none() && !is_url(pth)
+    }
+
+    fn is_local(&self) -> bool {
+        true
+    }
+
+    fn list(&self, dir: &Path, show_hidden: bool) -> Result<Vec<f_core::FileEntry>> {
+        read_dir(dir, show_hidden)
+    }
+
+    fn canonicalize(&self, pth: &Path) -> Result<PathBuf> {
+        fs::canonicalize(pth).map_err(|why| anyhow!("Failed to open '{}': {}", pth.display(), why))
+    }
+
+    fn stat(&self, pth: &Path) -> Result<Stat> {
+        let link_meta = fs::symlink_metadata(pth)
+            .map_err(|why| anyhow!("Failed to get metadata of '{}': {}", pth.display(), why))?;
+        // Для битой ссылки возвращаем информацию о самой ссылке
+        let meta = fs::metadata(pth).unwrap_or(link_meta.clone());
+
+        Ok(Stat {
+            file_type: FileType::from_fs_file_type(&meta.file_type()),
+            size: meta.len(),
+            mode: meta.permissions().mode() & 0o7777,
+            mtime: meta.modified().ok(),
+            link_target: if link_meta.is_symlink() {
+                fs::read_link(pth).ok()
+            } else {
+                None
+            },
+        })
+    }
+
+    fn open_read(&self, pth: &Path) -> Result<Box<dyn Read>> {
+        let file = File::open(pth)
+            .map_err(|why| anyhow!("Failed to open '{}': {}", pth.display(), why))?;
+        Ok(Box::new(file))
+    }
+
+    fn open_write(&self, pth: &Path, mode: u32) -> Result<Box<dyn Write>> {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(mode)
+            .open(pth)
+            .map_err(|why| anyhow!("Failed to create '{}': {}", pth.display(), why))?;
+        Ok(Box::new(file))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        fs::rename(from, to).map_err(|why| {
+            anyhow!(
+                "Failed to rename '{}' to '{}': {}",
+                from.display(),
+                to.display(),
+                why
+            )
+        })
+    }
+
+    fn rename_noreplace(&self, from: &Path, to: &Path) -> Result<()> {
+        rename_noreplace(from, to).map_err(|why| {
+            anyhow!(
+                "Failed to rename '{}' to '{}': {}",
+                from.display(),
+                to.display(),
+                why
+            )
+        })
+    }
+
+    fn remove(&self, pth: &Path) -> Result<()> {
+        remove_path(pth)
+    }
+
+    fn mkdir(&self, pth: &Path, mode: u32) -> Result<()> {
+        create_dir(pth, mode)
+    }
+
+    fn symlink(&self, target: &Path, pth: &Path) -> Result<()> {
+        std::os::unix::fs::symlink(target, pth)
+            .map_err(|why| anyhow!("Failed to create '{}': {}", pth.display(), why))
+    }
+}
+
+/// Rename the file only if `to` doesn't exist. On Linux it's done atomically
+/// by `renameat2(2)` with `RENAME_NOREPLACE` flag
+fn rename_noreplace(from: &Path, to: &Path) -> io::Result<()> {
+    #[cfg(target_os = "linux")]
+    match renameat2_noreplace(from, to) {
+        // Флаг поддерживается не всеми файловыми системами (например, NFS
+        // и FUSE) и ядрами
+        Err(why) if matches!(why.raw_os_error(), Some(libc::EINVAL | libc::ENOSYS)) => {}
+        rslt => return rslt,
+    }
+
+    // Проверка и переименование не атомарны
+    if fs::symlink_metadata(to).is_ok() {
+        return Err(io::ErrorKind::AlreadyExists.into());
+    }
+    fs::rename(from, to)
+}
+
+#[cfg(target_os = "linux")]
+fn renameat2_noreplace(from: &Path, to: &Path) -> io::Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let from = CString::new(from.as_os_str().as_bytes())?;
+    let to = CString::new(to.as_os_str().as_bytes())?;
+
+    // SAFETY: обе строки живут до конца вызова и завершаются нулём
+    let rslt = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            from.as_ptr(),
+            libc::AT_FDCWD,
+            to.as_ptr(),
+            libc::RENAME_NOREPLACE,
+        )
+    };
+    match rslt {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
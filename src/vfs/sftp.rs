@@ -0,0 +1,1020 @@
+//! Files on the remote host (SFTP version 3). The connection is made by the
+//! `ssh` program, so its config, keys and agent are used. Paths of the files
+//! look like `sftp://user@host:port/dir/file`
+//!
+//! Requests are synchronous and run in the UI thread, so the interface
+//! doesn't respond while the long operation (e.g. copying of the large file)
+//! runs over the slow connection.
+
+use super::{Stat, Vfs};
+use f_core::{FileEntry, FileType};
+
+use anyhow::{anyhow, Result};
+use std::{
+    cell::RefCell,
+    ffi::{OsStr, OsString},
+    fs,
+    io::{self, BufReader, BufWriter, Read, Write},
+    os::unix::ffi::{OsStrExt, OsStringExt},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    rc::Rc,
+    str::FromStr,
+    time::{Duration, SystemTime},
+};
+
+pub const SCHEME: &str = "sftp://";
+
+// Типы пакетов
+const INIT: u8 = 1;
+const VERSION: u8 = 2;
+const OPEN: u8 = 3;
+const CLOSE: u8 = 4;
+const READ: u8 = 5;
+const WRITE: u8 = 6;
+const LSTAT: u8 = 7;
+const OPENDIR: u8 = 11;
+const READDIR: u8 = 12;
+const REMOVE: u8 = 13;
+const MKDIR: u8 = 14;
+const RMDIR: u8 = 15;
+const REALPATH: u8 = 16;
+const STAT: u8 = 17;
+const RENAME: u8 = 18;
+const READLINK: u8 = 19;
+const SYMLINK: u8 = 20;
+const STATUS: u8 = 101;
+const HANDLE: u8 = 102;
+const DATA: u8 = 103;
+const NAME: u8 = 104;
+const ATTRS: u8 = 105;
+
+// Флаги открытия файла
+const FXF_READ: u32 = 0x01;
+const FXF_WRITE: u32 = 0x02;
+const FXF_CREAT: u32 = 0x08;
+const FXF_TRUNC: u32 = 0x10;
+
+// Поля атрибутов
+const ATTR_SIZE: u32 = 0x01;
+const ATTR_UIDGID: u32 = 0x02;
+const ATTR_PERMISSIONS: u32 = 0x04;
+const ATTR_ACMODTIME: u32 = 0x08;
+const ATTR_EXTENDED: u32 = 0x8000_0000;
+
+const STATUS_OK: u32 = 0;
+const STATUS_EOF: u32 = 1;
+
+/// Maximum size of the data in one read or write request (servers must
+/// support at least 32K packets)
+const CHUNK: usize = 32 * 1024;
+
+/// Maximum length of the received packet (the data of the largest packet
+/// sent by OpenSSH `sftp-server` with the header). Length is sent by the
+/// server, so it's checked before allocating the buffer
+const MAX_PACKET: usize = 256 * 1024 + 1024;
+
+/// Get the root of the remote file system (`sftp://user@host:port`) from the
+/// path of the file on it
+pub fn root(pth: &Path) -> Option<PathBuf> {
+    let rest = pth.to_str()?.strip_prefix(SCHEME)?;
+    let authority = rest.split('/').next().unwrap_or_default();
+    if authority.is_empty() {
+        return None;
+    }
+    Some(PathBuf::from(format!("{SCHEME}{authority}")))
+}
+
+/// Names of the hosts from the `ssh` config (patterns are skipped)
+pub fn config_hosts(config: &str) -> Vec<String> {
+    let mut hosts = Vec::new();
+    for line in config.lines() {
+        let line = line.trim();
+        let (key, value) = line
+            .split_once(|c: char| c.is_whitespace() || c == '=')
+            .unwrap_or((line, ""));
+        if !key.eq_ignore_ascii_case("host") {
+            continue;
+        }
+        for host in value
+            .trim_start_matches([' ', '\t', '='])
+            .split_whitespace()
+        {
+            if !host.contains(['*', '?', '!']) && !hosts.iter().any(|h| h == host) {
+                hosts.push(host.to_string());
+            }
+        }
+    }
+    hosts
+}
+
+/// Hosts from `~/.ssh/config`
+pub fn known_hosts() -> Vec<String> {
+    let config = f_core::utils::get_home().join(".ssh/config");
+    config_hosts(&fs::read_to_string(config).unwrap_or_default())
+}
+
+/// Attributes of the remote file
+#[derive(Debug, Default, Clone)]
+struct Attrs {
+    size: Option<u64>,
+    mode: Option<u32>,
+    mtime: Option<u32>,
+}
+
+impl Attrs {
+    fn file_type(&self) -> FileType {
+        let mode = self.mode.unwrap_or_default();
+        match mode & 0o170000 {
+            0o040000 => FileType::Directory,
+            0o120000 => FileType::Link,
+            0o100000 if mode & 0o111 != 0 => FileType::FileExecutable,
+            0o100000 => FileType::File,
+            _ => FileType::Special,
+        }
+    }
+
+    fn stat(&self, link_target: Option<PathBuf>) -> Stat {
+        Stat {
+            file_type: self.file_type(),
+            size: self.size.unwrap_or_default(),
+            mode: self.mode.unwrap_or_default() & 0o7777,
+            mtime: self
+                .mtime
+                .map(|mtime| SystemTime::UNIX_EPOCH + Duration::from_secs(mtime.into())),
+            link_target,
+        }
+    }
+}
+
+/// Outgoing packet
+struct Packet(Vec<u8>);
+
+impl Packet {
+    fn u32(mut self, value: u32) -> Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn u64(mut self, value: u64) -> Self {
+        self.0.extend_from_slice(&value.to_be_bytes());
+        self
+    }
+
+    fn bytes(mut self, value: &[u8]) -> Self {
+        self = self.u32(value.len() as u32);
+        self.0.extend_from_slice(value);
+        self
+    }
+
+    fn mode(self, mode: Option<u32>) -> Self {
+        match mode {
+            Some(mode) => self.u32(ATTR_PERMISSIONS).u32(mode),
+            None => self.u32(0),
+        }
+    }
+}
+
+/// Parser of the incoming packet
+struct Parser<'a>(&'a [u8]);
+
+impl Parser<'_> {
+    fn take(&mut self, len: usize) -> Result<&[u8]> {
+        if self.0.len() < len {
+            return Err(anyhow!("Malformed SFTP packet"));
+        }
+        let (value, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Ok(value)
+    }
+
+    fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_be_bytes(self.take(8)?.try_into()?))
+    }
+
+    fn bytes(&mut self) -> Result<Vec<u8>> {
+        let len = self.u32()? as usize;
+        Ok(self.take(len)?.to_vec())
+    }
+
+    fn attrs(&mut self) -> Result<Attrs> {
+        let flags = self.u32()?;
+        let mut attrs = Attrs::default();
+        if flags & ATTR_SIZE != 0 {
+            attrs.size = Some(self.u64()?);
+        }
+        if flags & ATTR_UIDGID != 0 {
+            self.take(8)?;
+        }
+        if flags & ATTR_PERMISSIONS != 0 {
+            attrs.mode = Some(self.u32()?);
+        }
+        if flags & ATTR_ACMODTIME != 0 {
+            self.take(4)?;
+            attrs.mtime = Some(self.u32()?);
+        }
+        if flags & ATTR_EXTENDED != 0 {
+            for _ in 0..self.u32()? {
+                self.bytes()?;
+                self.bytes()?;
+            }
+        }
+        Ok(attrs)
+    }
+
+    /// Names from the `NAME` reply with their attributes
+    fn names(&mut self) -> Result<Vec<(Vec<u8>, Attrs)>> {
+        (0..self.u32()?)
+            .map(|_| {
+                let name = self.bytes()?;
+                self.bytes()?; // вывод `ls -l`
+                Ok((name, self.attrs()?))
+            })
+            .collect()
+    }
+}
+
+/// Reply of the server
+enum Reply {
+    Status(u32, String),
+    Handle(Vec<u8>),
+    Data(Vec<u8>),
+    Names(Vec<(Vec<u8>, Attrs)>),
+    Attrs(Attrs),
+}
+
+impl Reply {
+    fn error(self) -> anyhow::Error {
+        match self {
+            Self::Status(_, msg) => anyhow!("{msg}"),
+            _ => anyhow!("Unexpected SFTP reply"),
+        }
+    }
+}
+
+/// SFTP session. Requests are sent one by one (the next one after the reply
+/// to the previous)
+struct Client {
+    reader: BufReader<Box<dyn Read>>,
+    writer: Box<dyn Write>,
+    id: u32,
+}
+
+impl Client {
+    fn new(reader: Box<dyn Read>, writer: Box<dyn Write>) -> Result<Self> {
+        let mut client = Self {
+            reader: BufReader::new(reader),
+            writer,
+            id: 0,
+        };
+        client.send(INIT, Packet(Vec::new()).u32(3))?;
+        let (kind, _) = client.recv()?;
+        if kind != VERSION {
+            return Err(anyhow!("Unexpected SFTP reply"));
+        }
+        Ok(client)
+    }
+
+    fn send(&mut self, kind: u8, body: Packet) -> io::Result<()> {
+        self.writer
+            .write_all(&(body.0.len() as u32 + 1).to_be_bytes())?;
+        self.writer.write_all(&[kind])?;
+        self.writer.write_all(&body.0)?;
+        self.writer.flush()
+    }
+
+    fn recv(&mut self) -> io::Result<(u8, Vec<u8>)> {
+        let mut len = [0; 4];
+        self.reader.read_exact(&mut len)?;
+        let len = u32::from_be_bytes(len) as usize;
+        if len > MAX_PACKET {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("SFTP packet is too large ({len} bytes)"),
+            ));
+        }
+        let mut data = vec![0; len];
+        self.reader.read_exact(&mut data)?;
+        if data.is_empty() {
+            return Err(io::Error::other("Malformed SFTP packet"));
+        }
+        let kind = data.remove(0);
+        Ok((kind, data))
+    }
+
+    /// Send the request (the body without id) and wait for the reply
+    fn request(&mut self, kind: u8, body: Packet) -> Result<Reply> {
+        self.id = self.id.wrapping_add(1);
+        let mut packet = Packet(Vec::new()).u32(self.id);
+        packet.0.extend(body.0);
+        let (kind, data) = self
+            .send(kind, packet)
+            .and_then(|_| self.recv())
+            .map_err(|why| anyhow!("SFTP connection lost: {why}"))?;
+
+        let mut parser = Parser(&data);
+        if parser.u32()? != self.id {
+            return Err(anyhow!("Unexpected SFTP reply"));
+        }
+        Ok(match kind {
+            STATUS => {
+                let code = parser.u32()?;
+                let msg = parser.bytes().unwrap_or_default();
+                Reply::Status(code, String::from_utf8_lossy(&msg).to_string())
+            }
+            HANDLE => Reply::Handle(parser.bytes()?),
+            DATA => Reply::Data(parser.bytes()?),
+            NAME => Reply::Names(parser.names()?),
+            ATTRS => Reply::Attrs(parser.attrs()?),
+            _ => return Err(anyhow!("Unexpected SFTP reply")),
+        })
+    }
+
+    /// Request which is answered only by the status
+    fn call(&mut self, kind: u8, body: Packet) -> Result<()> {
+        match self.request(kind, body)? {
+            Reply::Status(STATUS_OK, _) => Ok(()),
+            reply => Err(reply.error()),
+        }
+    }
+
+    fn handle(&mut self, kind: u8, body: Packet) -> Result<Vec<u8>> {
+        match self.request(kind, body)? {
+            Reply::Handle(handle) => Ok(handle),
+            reply => Err(reply.error()),
+        }
+    }
+
+    fn attrs(&mut self, kind: u8, pth: &[u8]) -> Result<Attrs> {
+        match self.request(kind, Packet(Vec::new()).bytes(pth))? {
+            Reply::Attrs(attrs) => Ok(attrs),
+            reply => Err(reply.error()),
+        }
+    }
+
+    /// Single name from the `REALPATH` or `READLINK` reply
+    fn name(&mut self, kind: u8, pth: &[u8]) -> Result<Vec<u8>> {
+        match self.request(kind, Packet(Vec::new()).bytes(pth))? {
+            Reply::Names(mut names) if !names.is_empty() => Ok(names.remove(0).0),
+            reply => Err(reply.error()),
+        }
+    }
+
+    fn read_dir(&mut self, pth: &[u8]) -> Result<Vec<(Vec<u8>, Attrs)>> {
+        let handle = self.handle(OPENDIR, Packet(Vec::new()).bytes(pth))?;
+        let mut entries = Vec::new();
+        let rslt = loop {
+            match self.request(READDIR, Packet(Vec::new()).bytes(&handle)) {
+                Ok(Reply::Names(names)) => entries.extend(names),
+                Ok(Reply::Status(STATUS_EOF, _)) => break Ok(entries),
+                Ok(reply) => break Err(reply.error()),
+                Err(why) => break Err(why),
+            }
+        };
+        let _ = self.call(CLOSE, Packet(Vec::new()).bytes(&handle));
+        rslt
+    }
+}
+
+/// Opened remote file
+struct RemoteFile {
+    client: Rc<RefCell<Client>>,
+    handle: Vec<u8>,
+    offset: u64,
+}
+
+impl Read for RemoteFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = buf.len().min(CHUNK);
+        let body = Packet(Vec::new())
+            .bytes(&self.handle)
+            .u64(self.offset)
+            .u32(len as u32);
+        match self.client.borrow_mut().request(READ, body) {
+            Ok(Reply::Data(data)) if data.len() <= len => {
+                buf[..data.len()].copy_from_slice(&data);
+                self.offset += data.len() as u64;
+                Ok(data.len())
+            }
+            Ok(Reply::Status(STATUS_EOF, _)) => Ok(0),
+            Ok(reply) => Err(io::Error::other(reply.error())),
+            Err(why) => Err(io::Error::other(why)),
+        }
+    }
+}
+
+impl Write for RemoteFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let data = &buf[..buf.len().min(CHUNK)];
+        let body = Packet(Vec::new())
+            .bytes(&self.handle)
+            .u64(self.offset)
+            .bytes(data);
+        self.client
+            .borrow_mut()
+            .call(WRITE, body)
+            .map_err(io::Error::other)?;
+        self.offset += data.len() as u64;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for RemoteFile {
+    fn drop(&mut self) {
+        let body = Packet(Vec::new()).bytes(&self.handle);
+        let _ = self.client.borrow_mut().call(CLOSE, body);
+    }
+}
+
+pub struct SftpFs {
+    /// `sftp://user@host:port`
+    root: PathBuf,
+    client: Rc<RefCell<Client>>,
+    /// `ssh` process
+    child: Option<Child>,
+}
+
+impl SftpFs {
+    /// Connect to the host from the `root` (`sftp://[user@]host[:port]`).
+    /// Passwords can't be entered, so the key authentication must be used
+    pub fn connect(root: &Path) -> Result<Self> {
+        let authority = root
+            .to_str()
+            .and_then(|root| root.strip_prefix(SCHEME))
+            .ok_or_else(|| anyhow!("Wrong SFTP address '{}'", root.display()))?;
+        let (user, host) = match authority.rsplit_once('@') {
+            Some((user, host)) => (Some(user), host),
+            None => (None, authority),
+        };
+        let (host, port) = match host.rsplit_once(':') {
+            Some((host, port)) if !host.ends_with(':') => (host, Some(port)),
+            _ => (host, None),
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+
+        let mut cmd = Command::new("ssh");
+        cmd.args(["-o", "BatchMode=yes", "-o", "ConnectTimeout=10"]);
+        if let Some(port) = port {
+            cmd.args(["-p", port]);
+        }
+        if let Some(user) = user {
+            cmd.args(["-l", user]);
+        }
+        let mut child = cmd
+            .args(["-s", "--", host, "sftp"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|why| anyhow!("Failed to run ssh: {why}"))?;
+
+        let (stdin, stdout) = (child.stdin.take().unwrap(), child.stdout.take().unwrap());
+        let mut stderr = child.stderr.take().unwrap();
+        match Client::new(Box::new(stdout), Box::new(stdin)) {
+            Ok(client) => {
+                // Предупреждения ssh не должны заполнить буфер канала
+                std::thread::spawn(move || io::copy(&mut stderr, &mut io::sink()));
+                Ok(Self {
+                    root: root.to_path_buf(),
+                    client: Rc::new(RefCell::new(client)),
+                    child: Some(child),
+                })
+            }
+            Err(why) => {
+                let _ = child.kill();
+                let _ = child.wait();
+                let mut msg = String::new();
+                let _ = stderr.read_to_string(&mut msg);
+                Err(anyhow!(
+                    "Failed to connect to '{host}': {}",
+                    match msg.trim() {
+                        "" => why.to_string(),
+                        msg => msg.lines().last().unwrap_or_default().to_string(),
+                    }
+                ))
+            }
+        }
+    }
+
+    /// Path of the file on the remote host
+    fn remote(&self, pth: &Path) -> Result<Vec<u8>> {
+        let rel = pth
+            .strip_prefix(&self.root)
+            .map_err(|_| anyhow!("'{}' is not on this host", pth.display()))?;
+        let mut remote = b"/".to_vec();
+        remote.extend_from_slice(rel.as_os_str().as_bytes());
+        Ok(remote)
+    }
+
+    /// Path of the file in the panel
+    fn local(&self, remote: &[u8]) -> PathBuf {
+        let rel = OsStr::from_bytes(remote.strip_prefix(b"/").unwrap_or(remote));
+        self.root.join(rel)
+    }
+
+    fn open(&self, pth: &Path, flags: u32, mode: Option<u32>) -> Result<RemoteFile> {
+        let body = Packet(Vec::new())
+            .bytes(&self.remote(pth)?)
+            .u32(flags)
+            .mode(mode);
+        let handle = self
+            .client
+            .borrow_mut()
+            .handle(OPEN, body)
+            .map_err(|why| anyhow!("Failed to open '{}': {}", pth.display(), why))?;
+        Ok(RemoteFile {
+            client: self.client.clone(),
+            handle,
+            offset: 0,
+        })
+    }
+
+    fn lstat(&self, pth: &Path) -> Result<Attrs> {
+        self.client.borrow_mut().attrs(LSTAT, &self.remote(pth)?)
+    }
+}
+
+impl Drop for SftpFs {
+    fn drop(&mut self) {
+        if let Some(child) = &mut self.child {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+impl Vfs for SftpFs {
+    fn contains(&self, pth: &Path) -> bool {
+        pth.starts_with(&self.root)
+    }
+
+    fn description(&self) -> Option<String> {
+        Some(format!(
+            "SFTP: {}",
+            self.root.to_string_lossy().trim_start_matches(SCHEME)
+        ))
+    }
+
+    /// Root of the host without the path is the home directory of the user
+    fn canonicalize(&self, pth: &Path) -> Result<PathBuf> {
+        let remote = match pth == self.root {
+            true => b".".to_vec(),
+            false => self.remote(pth)?,
+        };
+        let remote = self
+            .client
+            .borrow_mut()
+            .name(REALPATH, &remote)
+            .map_err(|why| anyhow!("Failed to open '{}': {}", pth.display(), why))?;
+        Ok(self.local(&remote))
+    }
+
+    fn list(&self, dir: &Path, show_hidden: bool) -> Result<Vec<FileEntry>> {
+        let remote = self.remote(dir)?;
+        let entries = self
+            .client
+            .borrow_mut()
+            .read_dir(&remote)
+            .map_err(|why| anyhow!("Failed to read '{}': {}", dir.display(), why))?;
+
+        let mut rows = Vec::new();
+        if remote != b"/" {
+            rows.push(FileEntry {
+                file_name: OsString::from_str(".. [UP]").unwrap(),
+                path: dir.parent().unwrap_or(&self.root).to_path_buf(),
+                byte_size: 4096,
+                file_type: FileType::ParentDirectory,
+                is_hidden: false,
+                link_target: None,
+                mtime: None,
+            });
+        }
+        for (name, attrs) in entries {
+            if name == b"." || name == b".." || (name.starts_with(b".") && !show_hidden) {
+                continue;
+            }
+            let pth = dir.join(OsString::from_vec(name));
+            let link_target = match attrs.file_type() {
+                FileType::Link => self.stat(&pth).ok().and_then(|stat| stat.link_target),
+                _ => None,
+            };
+            rows.push(attrs.stat(link_target).entry(pth));
+        }
+        Ok(rows)
+    }
+
+    fn stat(&self, pth: &Path) -> Result<Stat> {
+        let remote = self.remote(pth)?;
+        let mut client = self.client.borrow_mut();
+        let attrs = client
+            .attrs(LSTAT, &remote)
+            .map_err(|why| anyhow!("Failed to get metadata of '{}': {}", pth.display(), why))?;
+        if !matches!(attrs.file_type(), FileType::Link) {
+            return Ok(attrs.stat(None));
+        }
+
+        let target = client
+            .name(READLINK, &remote)
+            .ok()
+            .map(|target| PathBuf::from(OsString::from_vec(target)));
+        // Для битой ссылки возвращаем информацию о самой ссылке
+        Ok(client.attrs(STAT, &remote).unwrap_or(attrs).stat(target))
+    }
+
+    // Каждое чтение и запись - отдельный запрос с ожиданием ответа, поэтому
+    // данные передаются блоками максимального размера, а не по 8K из
+    // `io::copy()`
+    fn open_read(&self, pth: &Path) -> Result<Box<dyn Read>> {
+        let file = self.open(pth, FXF_READ, None)?;
+        Ok(Box::new(BufReader::with_capacity(CHUNK, file)))
+    }
+
+    fn open_write(&self, pth: &Path, mode: u32) -> Result<Box<dyn Write>> {
+        let flags = FXF_WRITE | FXF_CREAT | FXF_TRUNC;
+        let file = self.open(pth, flags, Some(mode))?;
+        Ok(Box::new(BufWriter::with_capacity(CHUNK, file)))
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let body = Packet(Vec::new())
+            .bytes(&self.remote(from)?)
+            .bytes(&self.remote(to)?);
+        self.client.borrow_mut().call(RENAME, body).map_err(|why| {
+            anyhow!(
+                "Failed to rename '{}' to '{}': {}",
+                from.display(),
+                to.display(),
+                why
+            )
+        })
+    }
+
+    fn rename_noreplace(&self, from: &Path, to: &Path) -> Result<()> {
+        // SSH_FXP_RENAME версии 3 не заменяет существующий файл
+        self.rename(from, to)
+    }
+
+    fn remove(&self, pth: &Path) -> Result<()> {
+        let remote = self.remote(pth)?;
+        let is_dir = matches!(self.lstat(pth)?.file_type(), FileType::Directory);
+        if is_dir {
+            for entry in self.list(pth, true)? {
+                if !entry.is_parent() {
+                    self.remove(&entry.path)?;
+                }
+            }
+        }
+
+        let kind = if is_dir { RMDIR } else { REMOVE };
+        self.client
+            .borrow_mut()
+            .call(kind, Packet(Vec::new()).bytes(&remote))
+            .map_err(|why| anyhow!("Failed to remove '{}': {}", pth.display(), why))
+    }
+
+    fn mkdir(&self, pth: &Path, mode: u32) -> Result<()> {
+        if self.lstat(pth).is_ok() {
+            return Err(anyhow!("'{}' already exists", pth.display()));
+        }
+
+        // Промежуточные директории создаются с правами по умолчанию
+        let mut dirs = pth
+            .ancestors()
+            .take_while(|dir| *dir != self.root && self.lstat(dir).is_err())
+            .collect::<Vec<_>>();
+        dirs.reverse();
+        for dir in dirs {
+            let body = Packet(Vec::new())
+                .bytes(&self.remote(dir)?)
+                .mode((dir == pth).then_some(mode));
+            self.client
+                .borrow_mut()
+                .call(MKDIR, body)
+                .map_err(|why| anyhow!("Failed to create '{}': {}", dir.display(), why))?;
+        }
+        Ok(())
+    }
+
+    fn symlink(&self, target: &Path, pth: &Path) -> Result<()> {
+        // OpenSSH передаёт путь ссылки вторым, а не первым, как в
+        // спецификации, и другие серверы делают так же
+        let body = Packet(Vec::new())
+            .bytes(target.as_os_str().as_bytes())
+            .bytes(&self.remote(pth)?);
+        self.client
+            .borrow_mut()
+            .call(SYMLINK, body)
+            .map_err(|why| anyhow!("Failed to create '{}': {}", pth.display(), why))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use f_core::test_utils::TempDir;
+    use std::os::unix::{fs::PermissionsExt, net::UnixStream};
+
+    /// Minimal SFTP server working with the local files (stand-in for the
+    /// `sftp-server` on the remote host)
+    fn serve(mut stream: UnixStream) -> io::Result<()> {
+        let mut handles = Vec::new();
+        loop {
+            let mut len = [0; 4];
+            if stream.read_exact(&mut len).is_err() {
+                return Ok(());
+            }
+            let len = u32::from_be_bytes(len) as usize;
+            if len > MAX_PACKET {
+                return Err(io::Error::from(io::ErrorKind::InvalidData));
+            }
+            let mut data = vec![0; len];
+            stream.read_exact(&mut data)?;
+            let mut req = Parser(&data[1..]);
+            let reply = match data[0] {
+                INIT => Packet(vec![VERSION]).u32(3),
+                kind => {
+                    let id = req.u32().unwrap();
+                    let mut reply = Packet(Vec::new()).u32(id);
+                    let kind = match reply_to(kind, &mut req, &mut handles, &mut reply) {
+                        Ok(STATUS) => {
+                            reply = reply.u32(STATUS_OK).bytes(b"");
+                            STATUS
+                        }
+                        Ok(kind) => kind,
+                        Err(why) => {
+                            let code = match why.kind() {
+                                io::ErrorKind::UnexpectedEof => STATUS_EOF,
+                                _ => 4,
+                            };
+                            reply = Packet(Vec::new()).u32(id).u32(code);
+                            reply = reply.bytes(why.to_string().as_bytes());
+                            STATUS
+                        }
+                    };
+                    let mut packet = Packet(vec![kind]);
+                    packet.0.extend(reply.0);
+                    packet
+                }
+            };
+            stream.write_all(&(reply.0.len() as u32).to_be_bytes())?;
+            stream.write_all(&reply.0)?;
+        }
+    }
+
+    fn path(req: &mut Parser) -> PathBuf {
+        PathBuf::from(OsString::from_vec(req.bytes().unwrap()))
+    }
+
+    fn attrs(meta: fs::Metadata) -> Packet {
+        Packet(Vec::new())
+            .u32(ATTR_SIZE | ATTR_PERMISSIONS)
+            .u64(meta.len())
+            .u32(meta.permissions().mode())
+    }
+
+    fn name(reply: &mut Packet, name: &OsStr, meta: Option<fs::Metadata>) {
+        let packet = Packet(std::mem::take(&mut reply.0))
+            .bytes(name.as_bytes())
+            .bytes(b"");
+        reply.0 = packet.0;
+        match meta {
+            Some(meta) => reply.0.extend(attrs(meta).0),
+            None => reply.0.extend(0_u32.to_be_bytes()),
+        }
+    }
+
+    /// Fill the reply to the request and get its type
+    fn reply_to(
+        kind: u8,
+        req: &mut Parser,
+        handles: &mut Vec<(PathBuf, Option<fs::ReadDir>)>,
+        reply: &mut Packet,
+    ) -> io::Result<u8> {
+        let status = |rslt: io::Result<()>| rslt.map(|_| STATUS);
+        let set_mode =
+            |pth: &Path, mode| fs::set_permissions(pth, fs::Permissions::from_mode(mode));
+        let mut push = |pth, dir, reply: &mut Packet| {
+            handles.push((pth, dir));
+            let handle = (handles.len() - 1).to_string();
+            reply
+                .0
+                .extend(Packet(Vec::new()).bytes(handle.as_bytes()).0);
+            Ok(HANDLE)
+        };
+
+        match kind {
+            REALPATH | READLINK => {
+                let pth = path(req);
+                let pth = match kind {
+                    REALPATH => fs::canonicalize(pth)?,
+                    _ => fs::read_link(pth)?,
+                };
+                reply.0.extend(1_u32.to_be_bytes());
+                name(reply, pth.as_os_str(), None);
+                Ok(NAME)
+            }
+            STAT | LSTAT => {
+                let pth = path(req);
+                let meta = match kind {
+                    STAT => fs::metadata(pth)?,
+                    _ => fs::symlink_metadata(pth)?,
+                };
+                reply.0.extend(attrs(meta).0);
+                Ok(ATTRS)
+            }
+            OPENDIR => {
+                let pth = path(req);
+                let dir = fs::read_dir(&pth)?;
+                push(pth, Some(dir), reply)
+            }
+            OPEN => {
+                let pth = path(req);
+                let flags = req.u32().unwrap();
+                let mode = req.attrs().unwrap().mode;
+                fs::OpenOptions::new()
+                    .read(true)
+                    .write(flags & FXF_WRITE != 0)
+                    .create(flags & FXF_CREAT != 0)
+                    .truncate(flags & FXF_TRUNC != 0)
+                    .open(&pth)?;
+                if let Some(mode) = mode {
+                    set_mode(&pth, mode)?;
+                }
+                push(pth, None, reply)
+            }
+            READDIR | READ | WRITE | CLOSE => {
+                let idx = String::from_utf8(req.bytes().unwrap()).unwrap();
+                let (pth, dir) = &mut handles[idx.parse::<usize>().unwrap()];
+                match kind {
+                    READDIR => {
+                        let entry = dir
+                            .as_mut()
+                            .unwrap()
+                            .next()
+                            .ok_or(io::ErrorKind::UnexpectedEof)??;
+                        reply.0.extend(1_u32.to_be_bytes());
+                        name(
+                            reply,
+                            &entry.file_name(),
+                            Some(entry.path().symlink_metadata()?),
+                        );
+                        Ok(NAME)
+                    }
+                    READ => {
+                        let offset = req.u64().unwrap() as usize;
+                        let len = req.u32().unwrap() as usize;
+                        let data = fs::read(pth)?;
+                        match data.get(offset..) {
+                            Some(rest) if !rest.is_empty() => {
+                                let data = &rest[..rest.len().min(len)];
+                                reply.0.extend(Packet(Vec::new()).bytes(data).0);
+                                Ok(DATA)
+                            }
+                            _ => Err(io::ErrorKind::UnexpectedEof.into()),
+                        }
+                    }
+                    WRITE => {
+                        let offset = req.u64().unwrap() as usize;
+                        let chunk = req.bytes().unwrap();
+                        let mut data = fs::read(&pth)?;
+                        data.resize(data.len().max(offset + chunk.len()), 0);
+                        data[offset..offset + chunk.len()].copy_from_slice(&chunk);
+                        status(fs::write(pth, data))
+                    }
+                    _ => status(Ok(())),
+                }
+            }
+            REMOVE => status(fs::remove_file(path(req))),
+            RMDIR => status(fs::remove_dir(path(req))),
+            MKDIR => {
+                let pth = path(req);
+                let mode = req.attrs().unwrap().mode.unwrap_or(0o755);
+                fs::create_dir(&pth)?;
+                status(set_mode(&pth, mode))
+            }
+            RENAME => {
+                let from = path(req);
+                status(fs::rename(from, path(req)))
+            }
+            SYMLINK => {
+                let target = path(req);
+                status(std::os::unix::fs::symlink(target, path(req)))
+            }
+            _ => Err(io::Error::other("Unsupported")),
+        }
+    }
+
+    fn connect() -> SftpFs {
+        let (client, server) = UnixStream::pair().unwrap();
+        std::thread::spawn(move || serve(server));
+        let client = Client::new(Box::new(client.try_clone().unwrap()), Box::new(client)).unwrap();
+        SftpFs {
+            root: PathBuf::from("sftp://user@host:2222"),
+            client: Rc::new(RefCell::new(client)),
+            child: None,
+        }
+    }
+
+    #[test]
+    fn url_test() {
+        assert_eq!(
+            root(Path::new("sftp://user@host:22/tmp/a")),
+            Some(PathBuf::from("sftp://user@host:22"))
+        );
+        assert_eq!(
+            root(Path::new("sftp://host")),
+            Some(PathBuf::from("sftp://host"))
+        );
+        assert_eq!(root(Path::new("sftp:///tmp")), None);
+        assert_eq!(root(Path::new("/tmp/sftp://host")), None);
+
+        let config = "Host *\n  User me\nHost build test-box\n  Port 22\nhost=arm !bad\nHostName x";
+        assert_eq!(config_hosts(config), ["build", "test-box", "arm"]);
+    }
+
+    #[test]
+    fn packet_limit_test() {
+        // Сервер сообщает о пакете длиной 4G
+        let reply = [0xff, 0xff, 0xff, 0xff, VERSION];
+        let client = Client::new(Box::new(io::Cursor::new(reply)), Box::new(io::sink()));
+        let err = client.err().unwrap().to_string();
+        assert!(err.contains("too large"), "{err}");
+    }
+
+    #[test]
+    fn sftp_test() {
+        let dir = TempDir::new("sftp");
+        fs::create_dir_all(dir.join("src")).unwrap();
+        fs::write(dir.join("src/a.txt"), "remote data").unwrap();
+        std::os::unix::fs::symlink("a.txt", dir.join("src/link")).unwrap();
+
+        let sftp = connect();
+        let root = sftp.root.join(dir.strip_prefix("/").unwrap());
+        assert_eq!(
+            sftp.canonicalize(&root.join("src/.")).unwrap(),
+            root.join("src")
+        );
+        assert_eq!(sftp.description().unwrap(), "SFTP: user@host:2222");
+
+        let entries = sftp.list(&root.join("src"), false).unwrap();
+        let mut names = entries
+            .iter()
+            .map(|entry| entry.file_name.to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, [".. [UP]", "a.txt", "link"]);
+        let link = sftp.stat(&root.join("src/link")).unwrap();
+        assert_eq!(link.link_target, Some(PathBuf::from("a.txt")));
+        assert_eq!(link.size, 11);
+
+        let mut data = String::new();
+        sftp.open_read(&root.join("src/a.txt"))
+            .unwrap()
+            .read_to_string(&mut data)
+            .unwrap();
+        assert_eq!(data, "remote data");
+
+        // Файл больше одного запроса
+        let big = (0..100_000).map(|n| n as u8).collect::<Vec<_>>();
+        sftp.mkdir(&root.join("new/sub"), 0o700).unwrap();
+        assert!(sftp.mkdir(&root.join("new/sub"), 0o700).is_err());
+        let mut writer = sftp.open_write(&root.join("new/sub/big"), 0o755).unwrap();
+        writer.write_all(&big).unwrap();
+        writer.flush().unwrap();
+        drop(writer);
+        assert_eq!(fs::read(dir.join("new/sub/big")).unwrap(), big);
+        let stat = sftp.stat(&root.join("new/sub/big")).unwrap();
+        assert!(matches!(stat.file_type, FileType::FileExecutable));
+        assert_eq!(sftp.stat(&root.join("new/sub")).unwrap().mode, 0o700);
+
+        // Ссылка на родительский каталог не зацикливает копирование
+        std::os::unix::fs::symlink("..", dir.join("new/sub/up")).unwrap();
+        crate::vfs::copy(
+            &crate::vfs::LocalFs,
+            &dir.join("new"),
+            &sftp,
+            &root.join("copy"),
+        )
+        .unwrap();
+        assert_eq!(fs::read(dir.join("copy/sub/big")).unwrap(), big);
+        assert_eq!(
+            fs::read_link(dir.join("copy/sub/up")).unwrap(),
+            Path::new("..")
+        );
+
+        sftp.rename(&root.join("new"), &root.join("renamed"))
+            .unwrap();
+        assert!(dir.join("renamed/sub/big").exists());
+        sftp.remove(&root.join("renamed")).unwrap();
+        assert!(!dir.join("renamed").exists());
+        assert!(sftp.stat(&root.join("renamed")).is_err());
+    }
+}